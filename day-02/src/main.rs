@@ -1,5 +1,7 @@
-use std::io::{self, BufRead};
-use std::str;
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+use intcode::loader;
 
 // Puzzle data type
 type Intcode = u32;
@@ -7,15 +9,13 @@ type Intcode = u32;
 // ----------------------------------------------------------------------------
 
 fn main() -> io::Result<()> {
-    let program: Vec<Intcode> = io::stdin()
-        .lock() // Give access to BufRead::split()
-        .split(b',')
-        .filter_map(|s| s.ok()) // Discard errors from split()
-        .filter_map(|b| {
-            // Parse and discard errors
-            Intcode::from_str_radix(str::from_utf8(&b).unwrap_or_default(), 10)
-                .ok()
-        })
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    let program: Vec<Intcode> = loader::parse(input.as_bytes())
+        .unwrap_or_else(|e| panic!("parse: {}", e))
+        .into_iter()
+        .map(|value| Intcode::try_from(value).expect("value out of range"))
         .collect();
 
     // Part 1
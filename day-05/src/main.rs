@@ -1,5 +1,8 @@
 use std::cell::Cell;
-use std::io;
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+use intcode::loader;
 
 // ----------------------------------------------------------------------------
 
@@ -19,13 +22,13 @@ const HALT: Intcode = 99;
 // ----------------------------------------------------------------------------
 
 fn main() -> io::Result<()> {
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).expect("read_line");
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
 
-    let program: Vec<Intcode> = line
-        .trim()
-        .split(',')
-        .map(|s| s.parse().expect("parse"))
+    let program: Vec<Intcode> = loader::parse(input.as_bytes())
+        .unwrap_or_else(|e| panic!("parse: {}", e))
+        .into_iter()
+        .map(|value| Intcode::try_from(value).expect("value out of range"))
         .collect();
 
     // Part 1
@@ -0,0 +1,62 @@
+// Compare phase search strategies on chains of 5 to 10 amplifiers.
+//
+// Run with `cargo bench --bench search`, from the `day-07` directory.
+
+use std::time::Instant;
+
+use day_07::intcode::*;
+use day_07::search::{self, ChainSearch};
+
+/// Example amplifier from Part One: outputs `10 * input + phase`.
+const AMPLIFIER: [Intcode; 17] = [
+    3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+];
+
+/// Exhaustive search gets too slow for longer chains.
+const MAX_EXHAUSTIVE: usize = 9;
+
+fn main() {
+    println!(
+        "{:>4} {:>12} {:>10} {:>12} {:>10} {:>12}",
+        "amps", "subsets", "runs", "sequences", "runs", "exhaustive"
+    );
+
+    for n in 5..=10 {
+        let phases: Vec<Intcode> = (0..n as Intcode).collect();
+
+        let start = Instant::now();
+        let mut chain = ChainSearch::new(&AMPLIFIER);
+        let best = chain.best_by_subsets(&phases);
+        let (subsets, subset_runs) = (start.elapsed(), chain.runs);
+
+        let start = Instant::now();
+        let mut chain = ChainSearch::new(&AMPLIFIER);
+        assert_eq!(chain.best_by_sequences(&phases), best);
+        let (sequences, sequence_runs) = (start.elapsed(), chain.runs);
+
+        let exhaustive = if n <= MAX_EXHAUSTIVE {
+            let start = Instant::now();
+            let found = search::exhaustive(&AMPLIFIER, n, 0..n as Intcode, run);
+            assert_eq!(found, best);
+            format!("{:?}", start.elapsed())
+        } else {
+            String::from("-")
+        };
+
+        println!(
+            "{:>4} {:>12?} {:>10} {:>12?} {:>10} {:>12}",
+            n, subsets, subset_runs, sequences, sequence_runs, exhaustive
+        );
+    }
+}
+
+/// Run the chain without memoisation.
+fn run(program: &[Intcode], phases: &[Intcode]) -> Intcode {
+    phases.iter().fold(0, |signal, phase| {
+        let mut process = Process::new(program);
+        process.input.push(*phase);
+        process.input.push(signal);
+        process.exec();
+        process.output.pop().expect("No output")
+    })
+}
@@ -0,0 +1,415 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::intcode::*;
+
+// Amplifier circuits, described as a graph of nodes connected by wires.
+//
+// Each node is either an amplifier, running its own copy of the Intcode
+// amplifier program with a given phase setting, or a summer, which waits for
+// one signal from each of its input wires and outputs their sum. Wires copy
+// every output signal of a node to the input of another node; an amplifier
+// with several input wires reads signals in the order they arrive.
+//
+// Circuits can be built with the API, or parsed from a text description:
+//
+//     # Nodes: NAME = amp PHASE | NAME = sum
+//     A = amp 5
+//     B = amp 6
+//     S = sum
+//
+//     # Wires, which can be chained: FROM -> TO [-> TO...]
+//     in -> A -> B -> S -> out
+//     A -> S
+//     S -> A
+//
+// The special `in` node sends the initial signal `0` to the nodes it's wired
+// to, and the last signal wired to `out` is the one sent to the thrusters.
+
+/// Identifies a node of a circuit.
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Amplifier { name: String, phase: Intcode },
+    Sum { name: String },
+}
+
+#[derive(Debug, Default)]
+pub struct Circuit {
+    nodes: Vec<Node>,
+    wires: Vec<(NodeId, NodeId)>,
+    inputs: Vec<NodeId>,  // Nodes that get the initial signal
+    outputs: Vec<NodeId>, // Nodes wired to the thrusters
+}
+
+/// Results of running a circuit.
+#[derive(Debug)]
+pub struct Run {
+    /// Last signal sent to the thrusters, if any.
+    pub thruster: Option<Intcode>,
+    /// All signals output by each node, in order.
+    pub history: Vec<Vec<Intcode>>,
+    /// Whether all amplifiers halted; if not, they were left waiting.
+    pub halted: bool,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Circuit {
+    pub fn new() -> Circuit {
+        Default::default()
+    }
+
+    /// Amplifiers connected in series, with a single pass of the signal.
+    pub fn chain(phases: &[Intcode]) -> Result<Circuit, String> {
+        if phases.is_empty() {
+            return Err(String::from("no amplifiers to chain"));
+        }
+
+        let mut circuit = Circuit::new();
+        let ids: Vec<_> = phases
+            .iter()
+            .enumerate()
+            .map(|(i, phase)| circuit.amplifier(&amp_name(i), *phase))
+            .collect();
+
+        for pair in ids.windows(2) {
+            circuit.connect(pair[0], pair[1]);
+        }
+        circuit.set_input(ids[0]);
+        circuit.set_output(ids[ids.len() - 1]);
+
+        Ok(circuit)
+    }
+
+    /// Amplifiers connected in series, with a feedback loop from the last one
+    /// back to the first one.
+    pub fn ring(phases: &[Intcode]) -> Result<Circuit, String> {
+        let mut circuit = Circuit::chain(phases)?;
+        circuit.connect(phases.len() - 1, 0);
+        Ok(circuit)
+    }
+
+    pub fn amplifier(&mut self, name: &str, phase: Intcode) -> NodeId {
+        self.add(Node::Amplifier {
+            name: name.to_string(),
+            phase,
+        })
+    }
+
+    pub fn sum(&mut self, name: &str) -> NodeId {
+        self.add(Node::Sum {
+            name: name.to_string(),
+        })
+    }
+
+    fn add(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    pub fn connect(&mut self, from: NodeId, to: NodeId) {
+        self.wires.push((from, to));
+    }
+
+    /// Send the initial signal to this node.
+    pub fn set_input(&mut self, id: NodeId) {
+        self.inputs.push(id);
+    }
+
+    /// Send the signals of this node to the thrusters.
+    pub fn set_output(&mut self, id: NodeId) {
+        self.outputs.push(id);
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Parse a text description of a circuit.
+    pub fn parse(text: &str) -> Result<Circuit, String> {
+        let mut circuit = Circuit::new();
+        let mut names = HashMap::new();
+
+        for (n, line) in text.lines().enumerate() {
+            let error = |msg: &str| format!("line {}: {}", n + 1, msg);
+
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.contains("->") {
+                let ends: Vec<&str> = line.split("->").map(str::trim).collect();
+                for pair in ends.windows(2) {
+                    match (pair[0], pair[1]) {
+                        ("in", "out") => {
+                            return Err(error("'in' wired to 'out'"))
+                        }
+                        ("in", to) => circuit.set_input(lookup(&names, to)?),
+                        (from, "out") => {
+                            circuit.set_output(lookup(&names, from)?)
+                        }
+                        (from, to) => circuit.connect(
+                            lookup(&names, from)?,
+                            lookup(&names, to)?,
+                        ),
+                    }
+                }
+                continue;
+            }
+
+            let (name, def) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(error("expected a node or wires")),
+            };
+            if name.is_empty() || name == "in" || name == "out" {
+                return Err(error(&format!("bad node name '{}'", name)));
+            }
+            if names.contains_key(name) {
+                return Err(error(&format!("duplicate node '{}'", name)));
+            }
+
+            let words: Vec<&str> = def.split_whitespace().collect();
+            let id = match words.as_slice() {
+                ["amp", phase] => match phase.parse() {
+                    Ok(phase) => circuit.amplifier(name, phase),
+                    Err(_) => return Err(error("bad phase setting")),
+                },
+                ["sum"] => circuit.sum(name),
+                _ => return Err(error("expected 'amp PHASE' or 'sum'")),
+            };
+            names.insert(name.to_string(), id);
+        }
+
+        if circuit.inputs.is_empty() || circuit.outputs.is_empty() {
+            return Err(String::from("missing 'in' or 'out' wires"));
+        }
+
+        Ok(circuit)
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Run the circuit until no node can make progress.
+    pub fn run(&self, program: &[Intcode]) -> Run {
+        // All wires, with `None` as the source of the ones from `in`
+        let wires: Vec<(Option<NodeId>, NodeId)> = self
+            .inputs
+            .iter()
+            .map(|to| (None, *to))
+            .chain(self.wires.iter().map(|(from, to)| (Some(*from), *to)))
+            .collect();
+
+        // Input wires of each node, for the summers
+        let sources: Vec<Vec<usize>> = (0..self.nodes.len())
+            .map(|id| (0..wires.len()).filter(|w| wires[*w].1 == id).collect())
+            .collect();
+
+        let mut processes: Vec<Option<Process>> = self
+            .nodes
+            .iter()
+            .map(|node| match node {
+                Node::Amplifier { phase, .. } => {
+                    let mut process = Process::new(program);
+                    process.input.push(*phase);
+                    Some(process)
+                }
+                Node::Sum { .. } => None,
+            })
+            .collect();
+        let mut halted = vec![false; self.nodes.len()];
+
+        // Pending signals of each summer, one queue per input wire
+        let mut pending: Vec<Vec<VecDeque<Intcode>>> = sources
+            .iter()
+            .map(|s| vec![VecDeque::new(); s.len()])
+            .collect();
+
+        let mut history = vec![Vec::new(); self.nodes.len()];
+        let mut thruster = None;
+
+        // Signals waiting to be delivered: (wire, value)
+        let mut signals: VecDeque<(usize, Intcode)> =
+            (0..self.inputs.len()).map(|w| (w, 0)).collect();
+
+        loop {
+            // Deliver signals
+            while let Some((wire, value)) = signals.pop_front() {
+                let to = wires[wire].1;
+                match &mut processes[to] {
+                    Some(process) => process.input.push(value),
+                    None => {
+                        let slot = sources[to]
+                            .iter()
+                            .position(|w| *w == wire)
+                            .expect("wire");
+                        pending[to][slot].push_back(value);
+                    }
+                }
+            }
+
+            // Run every node that can make progress
+            let mut outputs = Vec::new();
+
+            for id in 0..self.nodes.len() {
+                match &mut processes[id] {
+                    Some(process) => {
+                        while !halted[id] {
+                            match process.exec() {
+                                Status::Halt => halted[id] = true,
+                                Status::NewOutput => {
+                                    outputs.extend(
+                                        process
+                                            .output
+                                            .drain(..)
+                                            .map(|v| (id, v)),
+                                    );
+                                }
+                                Status::WantInput => break,
+                            }
+                        }
+                    }
+                    None => {
+                        let queues = &mut pending[id];
+                        while !queues.is_empty()
+                            && queues.iter().all(|q| !q.is_empty())
+                        {
+                            let sum = queues
+                                .iter_mut()
+                                .map(|q| q.pop_front().unwrap())
+                                .sum();
+                            outputs.push((id, sum));
+                        }
+                    }
+                }
+            }
+
+            if outputs.is_empty() {
+                break;
+            }
+
+            for (id, value) in outputs {
+                history[id].push(value);
+                if self.outputs.contains(&id) {
+                    thruster = Some(value);
+                }
+                for (wire, (from, _)) in wires.iter().enumerate() {
+                    if *from == Some(id) {
+                        signals.push_back((wire, value));
+                    }
+                }
+            }
+        }
+
+        let halted = processes
+            .iter()
+            .zip(&halted)
+            .all(|(process, halted)| process.is_none() || *halted);
+
+        Run {
+            thruster,
+            history,
+            halted,
+        }
+    }
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Amplifier { name, .. } | Node::Sum { name } => name,
+        }
+    }
+}
+
+/// Name of the `i`-th amplifier: `A` to `Z`, then `AA`, `AB`, and so on.
+fn amp_name(mut i: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (i % 26) as u8);
+        if i < 26 {
+            break;
+        }
+        i = i / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+fn lookup(
+    names: &HashMap<String, NodeId>,
+    name: &str,
+) -> Result<NodeId, String> {
+    names
+        .get(name)
+        .copied()
+        .ok_or_else(|| format!("unknown node '{}'", name))
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Amplifier program from Part One examples: outputs 10 * signal + phase
+    const AMPLIFIER: [Intcode; 17] = [
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+
+    fn run_text(text: &str) -> Run {
+        Circuit::parse(text).expect("parse").run(&AMPLIFIER)
+    }
+
+    #[test]
+    fn fan_in_from_in() {
+        // The summer waits for both the initial signal and amplifier A
+        let run = run_text("A = amp 1\nS = sum\nin -> A -> S -> out\nin -> S");
+        assert_eq!(run.history, [vec![1], vec![1]]);
+        assert_eq!(run.thruster, Some(1));
+
+        // A summer fed only by `in`
+        let run = run_text("S = sum\nA = amp 5\nin -> S -> A -> out");
+        assert_eq!(run.history, [vec![0], vec![5]]);
+        assert_eq!(run.thruster, Some(5));
+    }
+
+    #[test]
+    fn duplicate_wires() {
+        // Each wire is a separate input of the summer
+        let run = run_text("A = amp 2\nS = sum\nin -> A -> S -> out\nA -> S");
+        assert_eq!(run.history, [vec![2], vec![4]]);
+
+        let run = run_text("S = sum\nA = amp 3\nin -> S -> A -> out\nin -> S");
+        assert_eq!(run.history, [vec![0], vec![3]]);
+    }
+
+    #[test]
+    fn empty_chains() {
+        assert!(Circuit::chain(&[]).is_err());
+        assert!(Circuit::ring(&[]).is_err());
+
+        let run = Circuit::chain(&[4, 3]).unwrap().run(&AMPLIFIER);
+        assert_eq!(run.thruster, Some(43));
+        assert!(run.halted);
+    }
+
+    #[test]
+    fn amplifier_names() {
+        let names: Vec<String> = [0, 25, 26, 27, 701, 702]
+            .iter()
+            .map(|i| amp_name(*i))
+            .collect();
+        assert_eq!(names, ["A", "Z", "AA", "AB", "ZZ", "AAA"]);
+
+        let circuit = Circuit::chain(&[0; 30]).unwrap();
+        let mut names: Vec<&str> =
+            circuit.nodes().iter().map(|node| node.name()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), 30);
+    }
+}
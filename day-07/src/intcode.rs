@@ -12,9 +12,12 @@ pub struct Process {
     ip: Cell<usize>,   // Instruction Pointer, keeps track of execution
 }
 
-#[derive(PartialEq)]
+/// Why `exec()` stopped running a process. Calling it again resumes exactly
+/// where it stopped: after the `OUT` instruction, at the `IN` instruction that
+/// found no input, or at the `HALT` instruction.
+#[derive(Debug, PartialEq)]
 pub enum Status {
-    WaitForInput,
+    WantInput,
     NewOutput,
     Halt,
 }
@@ -69,7 +72,8 @@ impl Process {
                 }
                 Opcode::IN => {
                     if self.input.is_empty() {
-                        return Status::WaitForInput;
+                        self.revert_ip();
+                        return Status::WantInput;
                     } else {
                         let input = self.input.remove(0);
                         self.mem_write(self.next_ip(), input);
@@ -118,6 +122,7 @@ impl Process {
                     }
                 }
                 Opcode::HALT => {
+                    self.revert_ip();
                     return Status::Halt;
                 }
             }
@@ -154,4 +159,50 @@ impl Process {
         self.ip.set(self.ip.get() + 1);
         aux
     }
+
+    fn revert_ip(&self) {
+        self.ip.set(self.ip.get() - 1);
+    }
+}
+
+impl Process {
+    /// Run until the next output, and take the oldest value from `output`.
+    ///
+    /// Returns `None` if the process halts or wants input first.
+    pub fn run_until_output(&mut self) -> Option<Intcode> {
+        match self.exec() {
+            Status::NewOutput => Some(self.output.remove(0)),
+            _ => None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode_conformance::{assert_conforms, impl_machine, Level};
+
+    impl_machine!(Process, Status);
+
+    #[test]
+    fn conformance() {
+        assert_conforms::<Process>(Level::Day05);
+    }
+
+    #[test]
+    fn run_until_output() {
+        // Output the input plus one, forever
+        let program = [3, 13, 1001, 13, 1, 13, 4, 13, 1105, 1, 0, 99, 99, 0];
+
+        let mut process = Process::new(&program);
+        assert_eq!(process.run_until_output(), None);
+        process.input.extend_from_slice(&[1, 5]);
+        process.output.push(0);
+        assert_eq!(process.run_until_output(), Some(0));
+        assert_eq!(process.run_until_output(), Some(2));
+        assert_eq!(process.run_until_output(), None);
+        assert_eq!(process.output, [6]);
+    }
 }
@@ -0,0 +1,9 @@
+// Amplifier circuits and phase searches, shared by the solution and the
+// search benchmark.
+
+// Puzzle data type
+pub mod intcode;
+
+pub mod circuit;
+pub mod plot;
+pub mod search;
@@ -1,139 +1,271 @@
-use std::io;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
 
-use permute;
+use day_07::circuit::{Circuit, Run};
+use day_07::intcode::*;
+use day_07::plot::History;
+use day_07::search::{self, Best, ChainSearch};
+use intcode::loader;
 
 // ----------------------------------------------------------------------------
 
-// Puzzle data type
-mod intcode;
-use intcode::*;
+/// Command-line options:
+/// `[CIRCUIT] [--phases P,P,...] [--chart] [--csv FILE] [--svg FILE]`
+#[derive(Default)]
+struct Options {
+    circuit: Option<String>,
+    phases: Option<Vec<Intcode>>,
+    chart: bool,
+    csv: Option<String>,
+    svg: Option<String>,
+}
 
 // ----------------------------------------------------------------------------
 
 fn main() -> io::Result<()> {
-    fn parse_line(line: &str) -> Vec<Intcode> {
-        line.trim()
-            .split(',')
-            .map(|s| s.parse().expect("parse"))
-            .collect()
-    }
+    let options = Options::parse(env::args().skip(1))?;
 
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).expect("read_line");
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let program = parse_program(&input);
 
     // Part 1
     // ------
 
     // Tests
     assert_eq!(
-        part1(&parse_line(
+        part1(&parse_program(
             "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0"
         )),
-        43210
+        Best {
+            signal: 43210,
+            phases: vec![4, 3, 2, 1, 0]
+        }
     );
     assert_eq!(
-        part1(&parse_line("3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0")),
-        54321
+        part1(&parse_program("3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0")),
+        Best {
+            signal: 54321,
+            phases: vec![0, 1, 2, 3, 4]
+        }
     );
     assert_eq!(
-        part1(&parse_line("3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0")),
-        65210
+        part1(&parse_program("3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0")),
+        Best {
+            signal: 65210,
+            phases: vec![1, 0, 4, 3, 2]
+        }
     );
 
     // Puzzle answer
-    let answer1 = part1(&parse_line(&line));
-    println!("Part 1: {}", answer1);
-    assert_eq!(answer1, 199988);
+    let answer1 = part1(&program);
+    println!("Part 1: {} (phases: {:?})", answer1.signal, answer1.phases);
+    assert_eq!(answer1.signal, 199988);
+    assert_eq!(answer1, search::exhaustive(&program, 5, 0..5, run_chain));
 
     // Part 2
     // ------
 
     // Tests
     assert_eq!(
-        part2(&parse_line("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5")),
-        139629729
+        part2(&parse_program("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5")),
+        Best {
+            signal: 139629729,
+            phases: vec![9, 8, 7, 6, 5]
+        }
     );
     assert_eq!(
-        part2(&parse_line("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10")),
-        18216
+        part2(&parse_program("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10")),
+        Best {
+            signal: 18216,
+            phases: vec![9, 7, 8, 5, 6]
+        }
     );
 
     // Puzzle answer
-    let answer2 = part2(&parse_line(&line));
-    println!("Part 2: {}", answer2);
-    assert_eq!(answer2, 17519904);
+    let answer2 = part2(&program);
+    println!("Part 2: {} (phases: {:?})", answer2.signal, answer2.phases);
+    assert_eq!(answer2.signal, 17519904);
+
+    // Circuits
+    // --------
+
+    // Tests
+    let fan_in = Circuit::parse(
+        "A = amp 1\nB = amp 2\nS = sum\nC = amp 3\n\
+         in -> A -> S -> C -> out\nin -> B -> S",
+    )
+    .expect("parse");
+    let run = fan_in.run(&parse_program(
+        "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+    ));
+    assert_eq!(run.thruster, Some(33));
+    assert_eq!(run.history, [vec![1], vec![2], vec![3], vec![33]]);
+
+    // Both parts again, as circuits
+    let chain = Circuit::chain(&answer1.phases)
+        .expect("chain")
+        .run(&program);
+    assert_eq!(chain.thruster, Some(answer1.signal));
+    let ring = Circuit::ring(&answer2.phases).expect("ring").run(&program);
+    assert_eq!(ring.thruster, Some(answer2.signal));
+    assert!(ring.halted);
+
+    // Any other circuit from a description file, or a feedback loop with
+    // other phase settings, with optional exports of its signal history
+    let circuit = match &options.circuit {
+        Some(path) => Circuit::parse(&fs::read_to_string(path)?)
+            .map_err(|err| invalid_arg(format!("{}: {}", path, err)))?,
+        None => {
+            Circuit::ring(options.phases.as_ref().unwrap_or(&answer2.phases))
+                .map_err(invalid_arg)?
+        }
+    };
+    let custom = options.circuit.is_some() || options.phases.is_some();
+    let exports =
+        options.chart || options.csv.is_some() || options.svg.is_some();
+    if !custom && !exports {
+        return Ok(());
+    }
+
+    let run = circuit.run(&program);
+    if custom {
+        report(&circuit, &run);
+    }
+    if exports {
+        let history = History::new(&circuit, &run);
+        if options.chart {
+            print!("{}", history.ascii_chart(72, 20));
+        }
+        if let Some(path) = &options.csv {
+            fs::write(path, history.to_csv())?;
+        }
+        if let Some(path) = &options.svg {
+            fs::write(path, history.svg_chart(800, 500))?;
+        }
+    }
 
     Ok(())
 }
 
 // ----------------------------------------------------------------------------
 
-fn part1(program: &[Intcode]) -> Intcode {
-    permute::permute(vec![0, 1, 2, 3, 4])
-        .iter()
-        .map(|phases| {
-            // Set up
-
-            let mut processes: Vec<_> = phases
-                .iter()
-                .map(|phase| {
-                    let mut process = Process::new(program);
-                    process.input.push(*phase as Intcode);
-                    process
-                })
-                .collect();
+fn parse_program(text: &str) -> Vec<Intcode> {
+    loader::parse(text.as_bytes()).unwrap_or_else(|e| panic!("parse: {}", e))
+}
 
-            // Run
+// ----------------------------------------------------------------------------
 
-            let mut next_input = 0;
+fn part1(program: &[Intcode]) -> Best {
+    ChainSearch::new(program).best_by_subsets(&[0, 1, 2, 3, 4])
+}
 
-            for (i, _) in phases.iter().enumerate() {
-                processes[i].input.push(next_input);
-                let status = processes[i].exec();
-                assert!(status == Status::NewOutput); // Debug: ensure expected status
-                next_input = processes[i].output.pop().expect("No output");
-            }
+// ----------------------------------------------------------------------------
 
-            next_input
-        })
-        .max()
-        .unwrap()
+fn part2(program: &[Intcode]) -> Best {
+    search::exhaustive(program, 5, 5..10, run_loop)
 }
 
 // ----------------------------------------------------------------------------
 
-fn part2(program: &[Intcode]) -> Intcode {
-    permute::permute(vec![5, 6, 7, 8, 9])
+/// Set up one amplifier per phase setting.
+fn amplifiers(program: &[Intcode], phases: &[Intcode]) -> Vec<Process> {
+    phases
         .iter()
-        .map(|phases| {
-            // Set up
-
-            let mut processes: Vec<_> = phases
-                .iter()
-                .map(|phase| {
-                    let mut process = Process::new(program);
-                    process.input.push(*phase as Intcode);
-                    process
-                })
-                .collect();
+        .map(|phase| {
+            let mut process = Process::new(program);
+            process.input.push(*phase);
+            process
+        })
+        .collect()
+}
+
+/// Run the amplifiers in series, with a single pass of the signal.
+fn run_chain(program: &[Intcode], phases: &[Intcode]) -> Intcode {
+    let mut processes = amplifiers(program, phases);
+    let mut next_input = 0;
+
+    for process in processes.iter_mut() {
+        process.input.push(next_input);
+        next_input = process.run_until_output().expect("No output");
+    }
+
+    next_input
+}
+
+/// Run the amplifiers in a feedback loop, until they halt.
+fn run_loop(program: &[Intcode], phases: &[Intcode]) -> Intcode {
+    let mut processes = amplifiers(program, phases);
+    let mut next_input = 0;
+
+    for i in (0..phases.len()).cycle() {
+        processes[i].input.push(next_input);
+        match processes[i].run_until_output() {
+            Some(output) => next_input = output,
+            None => break,
+        }
+    }
+
+    next_input
+}
+
+// ----------------------------------------------------------------------------
+
+/// Print the thruster signal and the signals output by each node.
+fn report(circuit: &Circuit, run: &Run) {
+    match run.thruster {
+        Some(signal) => println!("Circuit: {}", signal),
+        None => println!("Circuit: no signal reached the thrusters"),
+    }
+    if !run.halted {
+        println!("(some amplifiers were left waiting for input)");
+    }
+
+    for (node, history) in circuit.nodes().iter().zip(&run.history) {
+        println!("  {:>8}: {:?}", node.name(), history);
+    }
+}
 
-            // Run
+// ----------------------------------------------------------------------------
 
-            let mut next_input = 0;
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> io::Result<Options> {
+        let mut options = Options::default();
 
-            for (i, _) in phases.iter().enumerate().cycle() {
-                processes[i].input.push(next_input);
-                let status = processes[i].exec();
-                if status == Status::Halt {
-                    break;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next().ok_or_else(|| {
+                    invalid_arg(format!("{} needs a value", arg))
+                })
+            };
+            match arg.as_str() {
+                "--phases" => {
+                    let phases = value()?
+                        .split(',')
+                        .map(|s| s.trim().parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid_arg("bad --phases".into()))?;
+                    options.phases = Some(phases);
+                }
+                "--chart" => options.chart = true,
+                "--csv" => options.csv = Some(value()?),
+                "--svg" => options.svg = Some(value()?),
+                _ if !arg.starts_with("--") && options.circuit.is_none() => {
+                    options.circuit = Some(arg)
+                }
+                _ => {
+                    return Err(invalid_arg(format!("unknown option {}", arg)))
                 }
-                next_input = processes[i].output.pop().expect("No output");
             }
+        }
 
-            next_input
-        })
-        .max()
-        .unwrap()
+        Ok(options)
+    }
+}
+
+fn invalid_arg(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
 }
 
 // ----------------------------------------------------------------------------
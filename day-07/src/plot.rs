@@ -0,0 +1,339 @@
+use std::fmt::Write;
+
+use crate::circuit::{Circuit, Run};
+use crate::intcode::*;
+
+// Time series of the signals output by each node of a circuit, one value per
+// iteration of the loop they are in, with exports to CSV and to line charts.
+//
+// Signals in a feedback loop tend to grow exponentially, so charts switch to a
+// logarithmic scale when all values are positive and span several orders of
+// magnitude.
+
+pub struct History {
+    pub names: Vec<String>,
+    pub signals: Vec<Vec<Intcode>>,
+}
+
+/// Smallest plot area of SVG charts, in pixels each way.
+const MIN_PLOT: usize = 20;
+
+/// Line colors for SVG charts, cycled through by series.
+const COLORS: [&str; 6] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b",
+];
+
+// ----------------------------------------------------------------------------
+
+impl History {
+    pub fn new(circuit: &Circuit, run: &Run) -> History {
+        History {
+            names: circuit
+                .nodes()
+                .iter()
+                .map(|node| node.name().to_string())
+                .collect(),
+            signals: run.history.clone(),
+        }
+    }
+
+    /// Number of iterations, i.e. the length of the longest series.
+    pub fn iterations(&self) -> usize {
+        self.signals.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// One row per iteration, one column per node. Nodes that didn't output a
+    /// signal in some iteration get an empty cell.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("iteration");
+        for name in &self.names {
+            csv.push(',');
+            csv.push_str(&csv_field(name));
+        }
+        csv.push('\n');
+
+        for i in 0..self.iterations() {
+            csv.push_str(&i.to_string());
+            for series in &self.signals {
+                csv.push(',');
+                if let Some(value) = series.get(i) {
+                    csv.push_str(&value.to_string());
+                }
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Chart drawn with characters, using the first letter of each node name
+    /// for its points. It's at least 2 characters wide and high.
+    pub fn ascii_chart(&self, width: usize, height: usize) -> String {
+        let (width, height) = (width.max(2), height.max(2));
+        let scale = Scale::new(self);
+        let mut grid = vec![vec![' '; width]; height];
+
+        for (name, series) in self.names.iter().zip(&self.signals) {
+            let mark = name.chars().next().unwrap_or('*');
+            for (i, value) in series.iter().enumerate() {
+                let x = scale.x(i, width - 1).round() as usize;
+                let y = scale.y(*value, height - 1).round() as usize;
+                grid[height - 1 - y][x] = mark;
+            }
+        }
+
+        let top = scale.label(scale.max);
+        let bottom = scale.label(scale.min);
+        let margin = top.len().max(bottom.len());
+
+        let mut chart = String::new();
+        for (row, line) in grid.iter().enumerate() {
+            let label = match row {
+                0 => &top,
+                r if r == height - 1 => &bottom,
+                _ => "",
+            };
+            let line: String = line.iter().collect();
+            writeln!(chart, "{:>m$} |{}", label, line, m = margin).unwrap();
+        }
+        writeln!(chart, "{:>m$} +{}", "", "-".repeat(width), m = margin)
+            .unwrap();
+        writeln!(
+            chart,
+            "{:>m$}  0{:>w$}",
+            "",
+            self.iterations().saturating_sub(1),
+            m = margin,
+            w = width - 1
+        )
+        .unwrap();
+
+        chart
+    }
+
+    /// Chart as an SVG document, with one polyline per node and a legend.
+    /// Documents too small for the margins and a minimal plot area are made
+    /// larger.
+    pub fn svg_chart(&self, width: usize, height: usize) -> String {
+        const MARGIN: usize = 60;
+        let scale = Scale::new(self);
+        let w = width.saturating_sub(2 * MARGIN).max(MIN_PLOT);
+        let h = height.saturating_sub(2 * MARGIN).max(MIN_PLOT);
+        let (width, height) = (w + 2 * MARGIN, h + 2 * MARGIN);
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#,
+            width, height
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<rect width="{}" height="{}" fill="white"/>"#,
+            width, height
+        )
+        .unwrap();
+
+        // Axes and their labels
+        writeln!(
+            svg,
+            r#"<path d="M{m},{m} V{b} H{r}" fill="none" stroke="black"/>"#,
+            m = MARGIN,
+            b = MARGIN + h,
+            r = MARGIN + w
+        )
+        .unwrap();
+        for (value, y) in &[(scale.max, MARGIN), (scale.min, MARGIN + h)] {
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#,
+                MARGIN - 5,
+                y + 4,
+                scale.label(*value)
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">iteration</text>"#,
+            MARGIN + w / 2,
+            MARGIN + h + 30
+        )
+        .unwrap();
+
+        // Series and legend
+        for (n, (name, series)) in
+            self.names.iter().zip(&self.signals).enumerate()
+        {
+            let color = COLORS[n % COLORS.len()];
+            let points: Vec<String> = series
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let x = MARGIN as f64 + scale.x(i, w);
+                    let y = (MARGIN + h) as f64 - scale.y(*value, h);
+                    format!("{:.1},{:.1}", x, y)
+                })
+                .collect();
+            writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{}"/>"#,
+                points.join(" "),
+                color
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+                MARGIN + w + 10,
+                MARGIN + 15 * n,
+                color,
+                name
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Text as a CSV field, quoted if it has separators, quotes or line breaks.
+fn csv_field(text: &str) -> String {
+    if text.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Mapping of iterations and signal values to chart coordinates.
+struct Scale {
+    min: Intcode,
+    max: Intcode,
+    iterations: usize,
+    log: bool,
+}
+
+impl Scale {
+    fn new(history: &History) -> Scale {
+        let values = history.signals.iter().flatten();
+        let min = values.clone().copied().min().unwrap_or(0);
+        let max = values.copied().max().unwrap_or(0);
+
+        Scale {
+            min,
+            max,
+            iterations: history.iterations(),
+            log: min > 0 && max / min >= 1000,
+        }
+    }
+
+    /// Horizontal position of an iteration, from 0 to `size`.
+    fn x(&self, i: usize, size: usize) -> f64 {
+        if self.iterations <= 1 {
+            return 0.0;
+        }
+        i as f64 * size as f64 / (self.iterations - 1) as f64
+    }
+
+    /// Vertical position of a value, from 0 (`min`) to `size` (`max`).
+    fn y(&self, value: Intcode, size: usize) -> f64 {
+        let f = |v: Intcode| {
+            if self.log {
+                (v as f64).ln()
+            } else {
+                v as f64
+            }
+        };
+        if self.max == self.min {
+            return 0.0;
+        }
+        (f(value) - f(self.min)) / (f(self.max) - f(self.min)) * size as f64
+    }
+
+    fn label(&self, value: Intcode) -> String {
+        if self.log {
+            format!("{} (log)", value)
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> History {
+        History {
+            names: vec![String::from("A"), String::from("B")],
+            signals: vec![vec![1, 2, 3], vec![10]],
+        }
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(history().to_csv(), "iteration,A,B\n0,1,10\n1,2,\n2,3,\n");
+
+        let history = History {
+            names: vec![String::from("in,out"), String::from("say \"hi\"")],
+            signals: vec![vec![1], vec![2]],
+        };
+        assert_eq!(
+            history.to_csv(),
+            "iteration,\"in,out\",\"say \"\"hi\"\"\"\n0,1,2\n"
+        );
+    }
+
+    #[test]
+    fn ascii_chart() {
+        assert_eq!(
+            history().ascii_chart(5, 3),
+            "10 |B    \n   |     \n 1 |A A A\n   +-----\n    0   2\n"
+        );
+
+        // Values spanning several orders of magnitude
+        let history = History {
+            names: vec![String::from("A")],
+            signals: vec![vec![1, 10, 100, 1000]],
+        };
+        assert_eq!(
+            history.ascii_chart(4, 4),
+            "1000 (log) |   A\n           |  A \n           | A  \n   \
+             1 (log) |A   \n           +----\n            0  3\n"
+        );
+
+        // Too small to fit anything, so made larger
+        assert_eq!(history.ascii_chart(0, 0), history.ascii_chart(2, 2));
+    }
+
+    #[test]
+    fn svg_chart() {
+        let svg = history().svg_chart(200, 160);
+        let lines: Vec<&str> = svg.lines().collect();
+
+        assert!(lines[0].contains(r#"width="200" height="160""#));
+        assert!(lines.contains(
+            &r##"<polyline points="60.0,100.0 100.0,95.6 140.0,91.1" fill="none" stroke="#1f77b4"/>"##
+        ));
+        assert!(lines.contains(
+            &r##"<polyline points="60.0,60.0" fill="none" stroke="#ff7f0e"/>"##
+        ));
+        assert!(lines
+            .contains(&r##"<text x="150" y="75" fill="#ff7f0e">B</text>"##));
+        assert!(lines
+            .contains(&r#"<text x="55" y="64" text-anchor="end">10</text>"#));
+        assert_eq!(lines.last(), Some(&"</svg>"));
+
+        let svg = history().svg_chart(0, 100);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="140" height="140""#));
+    }
+}
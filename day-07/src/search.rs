@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::intcode::*;
+
+// Searches for the phase settings that give the highest thruster signal.
+//
+// The exhaustive search tries every sequence of phase settings, which takes
+// factorial time in the number of amplifiers. For amplifiers in a chain there
+// is a faster way, since each amplifier only depends on its phase setting and
+// on the signal it gets from the previous one:
+//
+// - Amplifier outputs are memoised by (phase, input) pair, so that no copy of
+//   the program runs twice with the same inputs.
+// - If the amplifiers always output a higher signal for a higher input, then
+//   among all the sequences that use the same set of phase settings, only the
+//   one with the highest signal can lead to the best result; the others are
+//   pruned. This leaves one candidate per subset of phase settings.
+//
+// That's only done when it's proven: each amplifier runs once on a symbolic
+// input `x`, with every value kept as `scale * x + offset`, and it must output
+// such a value with a positive scale. Programs that branch on their input, or
+// do anything else that can't be followed that way, aren't proven, and the
+// search falls back to trying all sequences, still with memoised outputs.
+
+/// Best phase setting sequence found by a search, and its thruster signal.
+#[derive(Debug, PartialEq)]
+pub struct Best {
+    pub signal: Intcode,
+    pub phases: Vec<Intcode>,
+}
+
+impl Best {
+    /// Whether this result is better than `other`. On ties, the lowest
+    /// sequence in lexicographic order wins.
+    fn beats(&self, other: &Best) -> bool {
+        (self.signal, &other.phases) > (other.signal, &self.phases)
+    }
+
+    /// Whether this result is better than `other`, or there's no `other` yet.
+    fn beats_any(&self, other: &Option<Best>) -> bool {
+        match other {
+            Some(other) => self.beats(other),
+            None => true,
+        }
+    }
+}
+
+/// Value of an amplifier program as a function of its input `x`:
+/// `scale * x + offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Affine {
+    scale: Intcode,
+    offset: Intcode,
+}
+
+/// Instructions run on a symbolic input before giving up, in case of loops.
+const MAX_STEPS: usize = 100_000;
+
+// ----------------------------------------------------------------------------
+
+/// Try all sequences of `count` distinct phase settings taken from `phases`,
+/// in parallel, and return the one that gives the highest thruster signal.
+///
+/// On ties, the lowest sequence in lexicographic order wins, so results don't
+/// depend on how the work was split between threads.
+pub fn exhaustive<F>(
+    program: &[Intcode],
+    count: usize,
+    phases: Range<Intcode>,
+    run: F,
+) -> Best
+where
+    F: Fn(&[Intcode], &[Intcode]) -> Intcode + Sync,
+{
+    let phases: Vec<Intcode> = phases.collect();
+
+    sequences(&phases, count)
+        .into_par_iter()
+        .map(|phases| Best {
+            signal: run(program, &phases),
+            phases,
+        })
+        .reduce_with(|a, b| if b.beats(&a) { b } else { a })
+        .expect("No phase settings")
+}
+
+/// All ordered sequences of `count` distinct values taken from `values`.
+fn sequences(values: &[Intcode], count: usize) -> Vec<Vec<Intcode>> {
+    if count == 0 {
+        return vec![Vec::new()];
+    }
+
+    let mut result = Vec::new();
+
+    for (i, value) in values.iter().enumerate() {
+        let mut rest = values.to_vec();
+        rest.remove(i);
+
+        for mut sequence in sequences(&rest, count - 1) {
+            sequence.insert(0, *value);
+            result.push(sequence);
+        }
+    }
+
+    result
+}
+
+// ----------------------------------------------------------------------------
+
+/// Search over amplifiers in a chain, using each phase setting exactly once.
+pub struct ChainSearch<'a> {
+    program: &'a [Intcode],
+    cache: HashMap<(Intcode, Intcode), Option<Intcode>>,
+    /// Number of times the amplifier program has been run.
+    pub runs: usize,
+}
+
+impl<'a> ChainSearch<'a> {
+    pub fn new(program: &'a [Intcode]) -> ChainSearch<'a> {
+        ChainSearch {
+            program,
+            cache: HashMap::new(),
+            runs: 0,
+        }
+    }
+
+    /// Output of one amplifier, or `None` if it doesn't output a signal.
+    pub fn amplify(
+        &mut self,
+        phase: Intcode,
+        input: Intcode,
+    ) -> Option<Intcode> {
+        let program = self.program;
+        let runs = &mut self.runs;
+
+        *self.cache.entry((phase, input)).or_insert_with(|| {
+            *runs += 1;
+            let mut process = Process::new(program);
+            process.input.push(phase);
+            process.input.push(input);
+            match process.exec() {
+                Status::NewOutput => process.output.pop(),
+                _ => None,
+            }
+        })
+    }
+
+    /// Whether it's proven that, for every phase setting, a higher input gives
+    /// a higher output.
+    pub fn is_increasing(&self, phases: &[Intcode]) -> bool {
+        phases
+            .iter()
+            .all(|phase| match symbolic_output(self.program, *phase) {
+                Some(output) => output.scale > 0,
+                None => false,
+            })
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Keep only the best partial sequence for each subset of phase settings,
+    /// extending subsets one phase at a time. Falls back to trying every
+    /// sequence unless the amplifiers are proven to be increasing.
+    ///
+    /// With increasing amplifiers, the lowest of the best sequences is always
+    /// kept for its subset, so ties are broken as in the other searches.
+    pub fn best_by_subsets(&mut self, phases: &[Intcode]) -> Best {
+        if !self.is_increasing(phases) {
+            return self.best_by_sequences(phases);
+        }
+
+        let n = phases.len();
+        assert!(n < 32, "Too many phase settings");
+
+        // Best partial sequence for each subset, indexed by bitmask
+        let mut best: Vec<Option<Best>> =
+            (0..1usize << n).map(|_| None).collect();
+        best[0] = Some(Best {
+            signal: 0,
+            phases: Vec::new(),
+        });
+
+        // Subsets only grow, so masks are visited after all their subsets
+        for mask in 0..(1usize << n) {
+            let current = match best[mask].take() {
+                Some(current) => current,
+                None => continue,
+            };
+
+            for (i, phase) in phases.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    continue;
+                }
+                let signal = match self.amplify(*phase, current.signal) {
+                    Some(signal) => signal,
+                    None => continue,
+                };
+
+                let mut sequence = current.phases.clone();
+                sequence.push(*phase);
+                let next = Best {
+                    signal,
+                    phases: sequence,
+                };
+
+                let slot = &mut best[mask | (1 << i)];
+                if next.beats_any(slot) {
+                    *slot = Some(next);
+                }
+            }
+
+            best[mask] = Some(current);
+        }
+
+        best.pop().flatten().expect("No phase settings")
+    }
+
+    /// Try every sequence, sharing the outputs of common prefixes.
+    pub fn best_by_sequences(&mut self, phases: &[Intcode]) -> Best {
+        let mut best = None;
+        let mut sequence = Vec::with_capacity(phases.len());
+        let mut used = vec![false; phases.len()];
+
+        self.extend(phases, 0, &mut sequence, &mut used, &mut best);
+
+        best.expect("No phase settings")
+    }
+
+    fn extend(
+        &mut self,
+        phases: &[Intcode],
+        signal: Intcode,
+        sequence: &mut Vec<Intcode>,
+        used: &mut [bool],
+        best: &mut Option<Best>,
+    ) {
+        if sequence.len() == phases.len() {
+            let found = Best {
+                signal,
+                phases: sequence.clone(),
+            };
+            if found.beats_any(best) {
+                *best = Some(found);
+            }
+            return;
+        }
+
+        for (i, phase) in phases.iter().enumerate() {
+            if used[i] {
+                continue;
+            }
+            if let Some(next) = self.amplify(*phase, signal) {
+                used[i] = true;
+                sequence.push(*phase);
+                self.extend(phases, next, sequence, used, best);
+                sequence.pop();
+                used[i] = false;
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Affine {
+    fn constant(value: Intcode) -> Affine {
+        Affine {
+            scale: 0,
+            offset: value,
+        }
+    }
+
+    fn as_constant(self) -> Option<Intcode> {
+        if self.scale == 0 {
+            Some(self.offset)
+        } else {
+            None
+        }
+    }
+
+    fn add(self, other: Affine) -> Option<Affine> {
+        Some(Affine {
+            scale: self.scale.checked_add(other.scale)?,
+            offset: self.offset.checked_add(other.offset)?,
+        })
+    }
+
+    /// Product, unless both depend on the input.
+    fn mul(self, other: Affine) -> Option<Affine> {
+        let (factor, value) = match (self.as_constant(), other.as_constant()) {
+            (Some(factor), _) => (factor, other),
+            (_, Some(factor)) => (factor, self),
+            _ => return None,
+        };
+        Some(Affine {
+            scale: value.scale.checked_mul(factor)?,
+            offset: value.offset.checked_mul(factor)?,
+        })
+    }
+}
+
+/// First output of the amplifier program with a given phase setting, as a
+/// function of its input signal.
+///
+/// Returns `None` if the output isn't always such a function, or if that
+/// can't be told by following the program: when it compares, branches on, or
+/// addresses memory with values that depend on the input, multiplies two of
+/// them, or doesn't output anything.
+fn symbolic_output(program: &[Intcode], phase: Intcode) -> Option<Affine> {
+    let mut mem: Vec<Affine> = program
+        .iter()
+        .map(|value| Affine::constant(*value))
+        .collect();
+    let signal = Affine {
+        scale: 1,
+        offset: 0,
+    };
+    let mut inputs = vec![Affine::constant(phase), signal].into_iter();
+    let mut ip = 0;
+
+    for _ in 0..MAX_STEPS {
+        let intcode = mem.get(ip)?.as_constant()?;
+
+        // Value of parameter `i`, and address written by parameter `i`
+        let param = |mem: &[Affine], i: u32| {
+            let value = *mem.get(ip + i as usize)?;
+            match intcode / (10 as Intcode).pow(i + 1) % 10 {
+                0 => mem.get(value.as_constant()? as usize).copied(),
+                1 => Some(value),
+                _ => None,
+            }
+        };
+        let dest = |mem: &[Affine], i: u32| {
+            let addr = mem.get(ip + i as usize)?.as_constant()? as usize;
+            Some(addr).filter(|addr| *addr < mem.len())
+        };
+
+        match intcode % 100 {
+            opcode @ 1..=2 | opcode @ 7..=8 => {
+                let (a, b) = (param(&mem, 1)?, param(&mem, 2)?);
+                let value = match opcode {
+                    1 => a.add(b)?,
+                    2 => a.mul(b)?,
+                    7 => Affine::constant(
+                        (a.as_constant()? < b.as_constant()?) as Intcode,
+                    ),
+                    _ => Affine::constant(
+                        (a.as_constant()? == b.as_constant()?) as Intcode,
+                    ),
+                };
+                let addr = dest(&mem, 3)?;
+                mem[addr] = value;
+                ip += 4;
+            }
+            3 => {
+                let addr = dest(&mem, 1)?;
+                mem[addr] = inputs.next()?;
+                ip += 2;
+            }
+            4 => return param(&mem, 1),
+            opcode @ 5..=6 => {
+                let value = param(&mem, 1)?.as_constant()?;
+                let target = param(&mem, 2)?.as_constant()?;
+                if (value != 0) == (opcode == 5) {
+                    ip = target as usize;
+                } else {
+                    ip += 3;
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run a chain of amplifiers, without memoisation.
+    fn run_chain(program: &[Intcode], phases: &[Intcode]) -> Intcode {
+        phases.iter().fold(0, |signal, phase| {
+            let mut process = Process::new(program);
+            process.input.push(*phase);
+            process.input.push(signal);
+            process.run_until_output().expect("No output")
+        })
+    }
+
+    /// Check that all searches find the same sequence, and return it.
+    fn search(program: &[Intcode]) -> Best {
+        let phases = [0, 1, 2, 3, 4];
+        let best = exhaustive(program, 5, 0..5, run_chain);
+
+        assert_eq!(ChainSearch::new(program).best_by_subsets(&phases), best);
+        assert_eq!(ChainSearch::new(program).best_by_sequences(&phases), best);
+        best
+    }
+
+    #[test]
+    fn examples() {
+        let programs: [&[Intcode]; 3] = [
+            &[
+                3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+            ],
+            &[
+                3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23,
+                23, 1, 24, 23, 23, 4, 23, 99, 0, 0,
+            ],
+            &[
+                3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0,
+                33, 1002, 33, 7, 33, 1, 33, 31, 31, 1, 32, 31, 31, 4, 31, 99,
+                0, 0, 0,
+            ],
+        ];
+        let signals = [43210, 54321, 65210];
+
+        for (program, signal) in programs.iter().zip(&signals) {
+            assert!(ChainSearch::new(program).is_increasing(&[0, 1, 2, 3, 4]));
+            assert_eq!(search(program).signal, *signal);
+        }
+    }
+
+    #[test]
+    fn not_increasing() {
+        // Outputs 10 * x + phase below 50, and 1000 - x + phase from there, so
+        // the best chain isn't the one with the highest signal at each step
+        let program = [
+            3, 40, 3, 41, 1007, 41, 50, 42, 1005, 42, 22, 1002, 41, -1, 43,
+            1001, 43, 1000, 43, 1105, 1, 26, 1002, 41, 10, 43, 1, 43, 40, 43,
+            4, 43, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert!(!ChainSearch::new(&program).is_increasing(&[0]));
+        assert_eq!(
+            search(&program),
+            Best {
+                signal: 881,
+                phases: vec![0, 1, 2, 3, 4]
+            }
+        );
+
+        // Outputs the phase setting, so that all sequences ending in the
+        // highest one tie
+        let program = [3, 7, 3, 8, 4, 7, 99, 0, 0];
+        assert!(!ChainSearch::new(&program).is_increasing(&[0]));
+        assert_eq!(search(&program).phases, [0, 1, 2, 3, 4]);
+    }
+}
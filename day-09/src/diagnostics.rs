@@ -0,0 +1,121 @@
+use std::fmt;
+
+use crate::intcode::*;
+
+// Diagnostics for the BOOST self-test.
+//
+// In test mode, BOOST checks each opcode in turn, and outputs the instruction
+// being checked (opcode plus parameter modes, e.g. `203` for an input in
+// relative mode) whenever the check fails, before outputting the keycode.
+// Running it with a trace lets us find which instruction that was, and where
+// in the program it was last executed.
+
+/// Instruction reported as not working by BOOST.
+#[derive(Debug, PartialEq)]
+pub struct Malfunction {
+    /// Raw value output by BOOST.
+    pub code: Intcode,
+    /// Opcode and parameter modes the value stands for, if valid.
+    pub instruction: Option<(Opcode, Vec<MemMode>)>,
+    /// Address where that instruction was last executed before the report.
+    pub address: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub malfunctions: Vec<Malfunction>,
+    /// Last output, if the program halted normally.
+    pub keycode: Option<Intcode>,
+    /// Number of instructions executed.
+    pub steps: usize,
+}
+
+// ----------------------------------------------------------------------------
+
+/// Run BOOST in test mode, and decode its reports.
+pub fn diagnose(program: &[Intcode]) -> Report {
+    let mut process = Process::new(program);
+    process.enable_trace();
+    process.input.push(1);
+
+    // Number of steps executed by the time of each output
+    let mut reported_at = Vec::new();
+
+    let halted = loop {
+        match process.exec() {
+            Status::NewOutput => reported_at.push(process.trace().len()),
+            Status::Halt => break true,
+            Status::WantInput => break false,
+        }
+    };
+
+    let trace = process.trace();
+    let mut outputs = process.output.clone();
+    let keycode = if halted { outputs.pop() } else { None };
+
+    let malfunctions = outputs
+        .iter()
+        .zip(reported_at)
+        .map(|(code, steps)| Malfunction {
+            code: *code,
+            instruction: Process::decode(*code),
+            address: trace[..steps]
+                .iter()
+                .rev()
+                .skip(1) // The output instruction itself
+                .find(|step| step.intcode == *code)
+                .map(|step| step.ip),
+        })
+        .collect();
+
+    Report {
+        malfunctions,
+        keycode,
+        steps: trace.len(),
+    }
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.malfunctions.is_empty() && self.keycode.is_some()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "BOOST self-test, {} steps:", self.steps)?;
+
+        for m in &self.malfunctions {
+            write!(f, "  FAIL {}: ", m.code)?;
+            match &m.instruction {
+                Some((opcode, modes)) => {
+                    write!(f, "{:?}", opcode)?;
+                    if !modes.is_empty() {
+                        let modes: Vec<_> =
+                            modes.iter().map(|m| format!("{:?}", m)).collect();
+                        write!(f, " ({})", modes.join(", "))?;
+                    }
+                }
+                None => write!(f, "not a valid instruction")?,
+            }
+            match m.address {
+                Some(address) => {
+                    writeln!(f, ", last run at address {}", address)?
+                }
+                None => writeln!(f, ", never run")?,
+            }
+        }
+
+        match self.keycode {
+            Some(keycode) if self.malfunctions.is_empty() => {
+                writeln!(f, "  OK, keycode {}", keycode)
+            }
+            Some(keycode) => writeln!(f, "  Keycode {}", keycode),
+            None => writeln!(f, "  Program did not halt"),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
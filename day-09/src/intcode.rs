@@ -11,17 +11,29 @@ pub struct Process {
     mem: Vec<Intcode>, // Each process has its own memory
     ip: Cell<usize>,   // Instruction Pointer, keeps track of execution
     rb: usize,         // Relative base
+
+    trace: Option<Vec<Step>>, // Executed instructions, if tracing
+}
+
+/// Instruction executed by a traced process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub ip: usize,
+    pub intcode: Intcode,
 }
 
-#[derive(PartialEq)]
+/// Why `exec()` stopped running a process. Calling it again resumes exactly
+/// where it stopped: after the `OUT` instruction, at the `IN` instruction that
+/// found no input, or at the `HALT` instruction.
+#[derive(Debug, PartialEq)]
 pub enum Status {
-    WaitForInput,
+    WantInput,
     NewOutput,
     Halt,
 }
 
-#[derive(FromPrimitive)]
-enum Opcode {
+#[derive(FromPrimitive, Debug, Clone, Copy, PartialEq)]
+pub enum Opcode {
     ADD = 1,   // Addition
     MUL = 2,   // Multiplication
     IN = 3,    // Read input
@@ -35,8 +47,8 @@ enum Opcode {
 }
 
 // Memory access modes
-#[derive(FromPrimitive)]
-enum MemMode {
+#[derive(FromPrimitive, Debug, Clone, Copy, PartialEq)]
+pub enum MemMode {
     Position = 0,  // Read, Write
     Immediate = 1, // Read only
     Relative = 2,  // Read, Write
@@ -53,12 +65,29 @@ impl Process {
             mem,
             ip: Cell::new(0),
             rb: 0,
+            trace: None,
         }
     }
 
+    /// Start recording every executed instruction.
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+    pub fn trace(&self) -> &[Step] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
     pub fn exec(&mut self) -> Status {
         loop {
+            let ip = self.ip.get();
             let intcode = self.mem.get(self.next_ip()).expect("Bad address");
+            if let Some(trace) = &mut self.trace {
+                trace.push(Step {
+                    ip,
+                    intcode: *intcode,
+                });
+            }
             let opcode = Opcode::from_i64(intcode % 100).expect("Bad Opcode");
 
             match opcode {
@@ -76,7 +105,8 @@ impl Process {
                 }
                 Opcode::IN => {
                     if self.input.is_empty() {
-                        return Status::WaitForInput;
+                        self.revert_ip();
+                        return Status::WantInput;
                     } else {
                         let pmodes = Process::get_param_modes(intcode, 1);
                         let input = self.input.remove(0);
@@ -131,12 +161,22 @@ impl Process {
                     self.rb = (self.rb as i64 + p0) as usize;
                 }
                 Opcode::HALT => {
+                    self.revert_ip();
                     return Status::Halt;
                 }
             }
         }
     }
 
+    /// Split an instruction into its opcode and the modes of its parameters.
+    pub fn decode(intcode: Intcode) -> Option<(Opcode, Vec<MemMode>)> {
+        let opcode = Opcode::from_i64(intcode % 100)?;
+        let modes = (0..opcode.param_count())
+            .map(|i| MemMode::from_i64((intcode / 10_i64.pow(i + 2)) % 10))
+            .collect::<Option<_>>()?;
+        Some((opcode, modes))
+    }
+
     fn get_param_modes(intcode: &Intcode, count: u32) -> Vec<MemMode> {
         (0..count)
             .map(|i| {
@@ -179,4 +219,63 @@ impl Process {
         self.ip.set(self.ip.get() + 1);
         aux
     }
+
+    fn revert_ip(&self) {
+        self.ip.set(self.ip.get() - 1);
+    }
+}
+
+impl Process {
+    /// Run the process to completion with the given inputs, and take all of
+    /// its outputs.
+    ///
+    /// Panics if the process wants more input than that.
+    pub fn run_to_completion(&mut self, inputs: &[Intcode]) -> Vec<Intcode> {
+        self.input.extend_from_slice(inputs);
+        loop {
+            match self.exec() {
+                Status::NewOutput => (),
+                Status::WantInput => panic!("Input underflow"),
+                Status::Halt => return self.output.drain(..).collect(),
+            }
+        }
+    }
+}
+
+impl Opcode {
+    pub fn param_count(self) -> u32 {
+        match self {
+            Opcode::ADD | Opcode::MUL | Opcode::LT | Opcode::EQ => 3,
+            Opcode::JIT | Opcode::JIF => 2,
+            Opcode::IN | Opcode::OUT | Opcode::RBO => 1,
+            Opcode::HALT => 0,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode_conformance::{assert_conforms, impl_machine, Level};
+
+    impl_machine!(Process, Status);
+
+    #[test]
+    fn conformance() {
+        assert_conforms::<Process>(Level::Day09);
+    }
+
+    #[test]
+    fn run_to_completion() {
+        // Output the input plus one
+        let program = [3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+
+        assert_eq!(Process::new(&program).run_to_completion(&[1]), [2]);
+        assert!(std::panic::catch_unwind(|| {
+            Process::new(&program).run_to_completion(&[])
+        })
+        .is_err());
+    }
 }
@@ -1,26 +1,31 @@
-use std::io;
+use std::io::{self, Read};
+
+use ::intcode::loader;
 
 // ----------------------------------------------------------------------------
 
 // Puzzle data type
 mod intcode;
-use intcode::*;
+use crate::intcode::*;
+
+mod diagnostics;
+use diagnostics::diagnose;
 
 // ----------------------------------------------------------------------------
 
 fn main() -> io::Result<()> {
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).expect("read_line");
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
 
     // Part 1
 
-    let answer1 = part1(&parse_line(&line));
+    let answer1 = part1(&parse_program(&input));
     println!("Part 1: {}", answer1);
     assert_eq!(answer1, 3280416268);
 
     // Part 2
 
-    let answer2 = part2(&parse_line(&line));
+    let answer2 = part2(&parse_program(&input));
     println!("Part 2: {}", answer2);
     assert_eq!(answer2, 80210);
 
@@ -29,39 +34,27 @@ fn main() -> io::Result<()> {
 
 // ----------------------------------------------------------------------------
 
-fn parse_line(line: &str) -> Vec<Intcode> {
-    line.trim()
-        .split(',')
-        .map(|s| s.parse().expect("parse"))
-        .collect()
+fn parse_program(text: &str) -> Vec<Intcode> {
+    loader::parse(text.as_bytes()).unwrap_or_else(|e| panic!("parse: {}", e))
 }
 
 // ----------------------------------------------------------------------------
 
 fn part1(program: &[Intcode]) -> Intcode {
-    let mut process = Process::new(&program);
-
-    process.input.push(1);
-
-    while process.exec() != Status::Halt {}
+    let report = diagnose(program);
 
-    if process.output.len() > 1 {
-        panic!("Malfunctioning opcodes: {:?}", process.output);
+    if !report.passed() {
+        print!("{}", report);
+        panic!("BOOST self-test failed");
     }
 
-    process.output[0]
+    report.keycode.unwrap()
 }
 
 // ----------------------------------------------------------------------------
 
 fn part2(program: &[Intcode]) -> Intcode {
-    let mut process = Process::new(&program);
-
-    process.input.push(2);
-
-    while process.exec() != Status::Halt {}
-
-    process.output[0]
+    Process::new(program).run_to_completion(&[2])[0]
 }
 
 // ----------------------------------------------------------------------------
@@ -72,7 +65,7 @@ mod tests {
 
     #[test]
     fn part1_quine() {
-        let program = parse_line(
+        let program = parse_program(
             "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
         );
         let mut process = Process::new(&program);
@@ -81,7 +74,7 @@ mod tests {
             let status = process.exec();
 
             match status {
-                Status::WaitForInput => panic!("Unexpected input"),
+                Status::WantInput => panic!("Unexpected input"),
                 Status::NewOutput => (),
                 Status::Halt => break,
             }
@@ -92,14 +85,14 @@ mod tests {
 
     #[test]
     fn part1_16digit() {
-        let program = parse_line("1102,34915192,34915192,7,4,7,99,0");
+        let program = parse_program("1102,34915192,34915192,7,4,7,99,0");
         let mut process = Process::new(&program);
 
         loop {
             let status = process.exec();
 
             match status {
-                Status::WaitForInput => panic!("Unexpected input"),
+                Status::WantInput => panic!("Unexpected input"),
                 Status::NewOutput => (),
                 Status::Halt => break,
             }
@@ -111,14 +104,14 @@ mod tests {
 
     #[test]
     fn part1_number() {
-        let program = parse_line("104,1125899906842624,99");
+        let program = parse_program("104,1125899906842624,99");
         let mut process = Process::new(&program);
 
         loop {
             let status = process.exec();
 
             match status {
-                Status::WaitForInput => panic!("Unexpected input"),
+                Status::WantInput => panic!("Unexpected input"),
                 Status::NewOutput => (),
                 Status::Halt => break,
             }
@@ -127,6 +120,30 @@ mod tests {
         assert_eq!(process.output.len(), 1);
         assert_eq!(process.output[0], 1125899906842624);
     }
+
+    #[test]
+    fn boost_self_test() {
+        let report = diagnose(&parse_program(include_str!("../input.txt")));
+
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.keycode, Some(3280416268));
+    }
+
+    #[test]
+    fn boost_malfunction() {
+        // Input in relative mode, then report it as broken and halt
+        let program = parse_program("109,50,203,0,104,203,104,42,99");
+        let report = diagnose(&program);
+
+        assert!(!report.passed());
+        assert_eq!(report.keycode, Some(42));
+        assert_eq!(report.malfunctions.len(), 1);
+        assert_eq!(
+            report.malfunctions[0].instruction,
+            Some((Opcode::IN, vec![MemMode::Relative]))
+        );
+        assert_eq!(report.malfunctions[0].address, Some(2));
+    }
 }
 
 // ----------------------------------------------------------------------------
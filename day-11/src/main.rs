@@ -1,15 +1,26 @@
 use std::collections::HashMap;
-use std::io;
+use std::env;
+use std::io::{self, Read};
+use std::time::Duration;
 
+use ::intcode::loader;
 use euclid::{point2, Angle};
-use turtle::Turtle;
+use recorder::{Palette, Recorder};
 
 // ----------------------------------------------------------------------------
 
 // Puzzle data types
 
 mod intcode;
-use intcode::*;
+use crate::intcode::*;
+
+mod ocr;
+
+mod render;
+use render::{Ansi, Headless, Recording, Renderer, Svg, TurtleWindow};
+
+mod stats;
+use stats::Stats;
 
 type Point2D = euclid::default::Point2D<i32>;
 type Vector2D = euclid::default::Vector2D<i32>;
@@ -21,56 +32,211 @@ const WHITE: Intcode = 1;
 const LEFT: Intcode = 0;
 const RIGHT: Intcode = 1;
 
+/// Command-line options: `[--output turtle|ansi|headless] [--svg FILE]`
+/// `[--record FILE] [--cell PIXELS] [--palette COLORS] [--stats DIR]`
+#[derive(Default)]
+struct Options {
+    output: Option<Output>,
+    svg: Option<String>,
+    stats: Option<String>,
+    record: Option<String>,
+    cell: Option<usize>,
+    palette: Option<Palette>,
+}
+
+/// Where the robot is drawn as it paints, besides any files saved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Output {
+    Headless,
+    Turtle,
+    Ansi,
+}
+
 // Config
 
-const TURTLE_SIZE: f64 = 5.0;
-// const TURTLE_SPEED: i32 = 8;  // Range: [1, 25]
-const TURTLE_SPEED: &str = "instant";
+const ANSI_DELAY: u64 = 1; // After each panel painted on the terminal, in ms
+
+// Recording colors: unpainted (the turtle background), black, white, robot
+const RECORD_PALETTE: &str = "808080,000000,ffffff,ff2020";
+const RECORD_DELAY: u32 = 40; // Between frames, in ms
+const HEAT_MAP_CELL: usize = 4; // Size of panels in the heat map
 
 // ----------------------------------------------------------------------------
 
 fn main() -> io::Result<()> {
-    turtle::start();
+    let options = Options::parse(env::args().skip(1))?;
+    if options.output() == Output::Turtle {
+        turtle::start();
+    }
 
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).expect("read_line");
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
 
     // Part 1
 
-    let answer1 = part1(&parse_line(&line));
+    let mut stats = Stats::new();
+    let mut renderers = vec![options.live()?];
+    if options.stats.is_some() {
+        renderers.push(Box::new(&mut stats));
+    }
+
+    let answer1 = part1(&parse_program(&input), &mut renderers)?;
     println!("Part 1: {}", answer1);
     assert_eq!(answer1, 2883);
 
-    // Part 2
+    drop(renderers);
+    if let Some(dir) = &options.stats {
+        print!("{}", stats);
+        stats.save(dir, options.cell.unwrap_or(HEAT_MAP_CELL))?;
+    }
 
-    let answer2 = part2(&parse_line(&line));
-    println!("Part 2:\n{}", answer2);
-    // assert_eq!(answer2, "LEPCPLGZ");
+    // Part 2, saved to files if asked to
+
+    let mut renderers = vec![options.live()?];
+    if let Some(path) = &options.record {
+        renderers.push(Box::new(Recording::new(options.recorder(), path)));
+    }
+    if let Some(path) = &options.svg {
+        renderers.push(Box::new(Svg::new(path)));
+    }
+
+    let painted = paint_ship(&parse_program(&input), WHITE, &mut renderers)?;
+    print!("{}", hull(&painted));
+    let answer2 = part2(&painted).unwrap_or_else(|err| panic!("{}", err));
+    println!("Part 2: {}", answer2);
+    assert_eq!(answer2, "LEPCPLGZ");
 
     Ok(())
 }
 
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> io::Result<Options> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--output" => match args.next().as_deref() {
+                    Some("headless") => options.output = Some(Output::Headless),
+                    Some("turtle") => options.output = Some(Output::Turtle),
+                    Some("ansi") => options.output = Some(Output::Ansi),
+                    _ => return Err(invalid_arg("bad --output".into())),
+                },
+                "--svg" => {
+                    let path = args.next().ok_or_else(|| {
+                        invalid_arg("missing --svg path".into())
+                    })?;
+                    options.svg = Some(path);
+                }
+                "--stats" => {
+                    let path = args.next().ok_or_else(|| {
+                        invalid_arg("missing --stats path".into())
+                    })?;
+                    options.stats = Some(path);
+                }
+                "--record" => {
+                    let path = args.next().ok_or_else(|| {
+                        invalid_arg("missing --record path".into())
+                    })?;
+                    options.record = Some(path);
+                }
+                "--cell" => {
+                    let cell = args
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .filter(|cell| *cell > 0)
+                        .ok_or_else(|| invalid_arg("bad --cell".into()))?;
+                    options.cell = Some(cell);
+                }
+                "--palette" => {
+                    let palette = args
+                        .next()
+                        .and_then(|s| Palette::parse(&s))
+                        .filter(|palette| palette.len() >= 4)
+                        .ok_or_else(|| invalid_arg("bad --palette".into()))?;
+                    options.palette = Some(palette);
+                }
+                _ => {
+                    return Err(invalid_arg(format!("unknown option {}", arg)))
+                }
+            }
+        }
+
+        let images = options.record.is_some() || options.stats.is_some();
+        if options.cell.is_some() && !images {
+            return Err(invalid_arg("--cell needs --record or --stats".into()));
+        }
+        if options.palette.is_some() && options.record.is_none() {
+            return Err(invalid_arg("--palette needs --record".into()));
+        }
+
+        Ok(options)
+    }
+
+    /// Where to draw the robot: with the turtle, unless it's saved to files.
+    fn output(&self) -> Output {
+        match self.output {
+            Some(output) => output,
+            None if self.record.is_some() || self.svg.is_some() => {
+                Output::Headless
+            }
+            None => Output::Turtle,
+        }
+    }
+
+    /// Renderer to draw the robot as it paints.
+    fn live(&self) -> io::Result<Box<dyn Renderer>> {
+        Ok(match self.output() {
+            Output::Headless => Box::new(Headless),
+            Output::Turtle => Box::new(TurtleWindow::new()),
+            Output::Ansi => {
+                Box::new(Ansi::new(Duration::from_millis(ANSI_DELAY))?)
+            }
+        })
+    }
+
+    fn recorder(&self) -> Recorder {
+        let palette = self.palette.clone().unwrap_or_else(|| {
+            Palette::parse(RECORD_PALETTE).expect("palette")
+        });
+        let recorder = Recorder::new(palette).delay_ms(RECORD_DELAY);
+
+        match self.cell {
+            Some(cell) => recorder.cell_size(cell),
+            None => recorder,
+        }
+    }
+}
+
+fn invalid_arg(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
 // ----------------------------------------------------------------------------
 
-fn parse_line(line: &str) -> Vec<Intcode> {
-    line.trim()
-        .split(',')
-        .map(|s| s.parse().expect("parse"))
-        .collect()
+fn parse_program(text: &str) -> Vec<Intcode> {
+    loader::parse(text.as_bytes()).unwrap_or_else(|e| panic!("parse: {}", e))
 }
 
 // ----------------------------------------------------------------------------
 
-fn part1(program: &[Intcode]) -> usize {
-    let painted = paint_ship(program, BLACK, true);
+fn part1<R: Renderer + ?Sized>(
+    program: &[Intcode],
+    renderer: &mut R,
+) -> io::Result<usize> {
+    let painted = paint_ship(program, BLACK, renderer)?;
 
-    painted.keys().count()
+    Ok(panels(&painted).len())
 }
 
 // ----------------------------------------------------------------------------
 
-fn part2(program: &[Intcode]) -> String {
-    let painted = paint_ship(program, WHITE, true);
+fn part2(painted: &[(Point2D, Intcode)]) -> Result<String, ocr::Unreadable> {
+    ocr::read_panels(&panels(painted))
+}
+
+/// Text drawing of the hull, with `#` for white panels.
+fn hull(painted: &[(Point2D, Intcode)]) -> String {
+    let painted = panels(painted);
 
     let min_x = painted.keys().map(|p| p.x).min().unwrap_or(0);
     let max_x = painted.keys().map(|p| p.x).max().unwrap_or(0);
@@ -96,29 +262,21 @@ fn part2(program: &[Intcode]) -> String {
 
 // ----------------------------------------------------------------------------
 
-fn paint_ship(
+/// Final color of each panel painted at least once.
+fn panels(painted: &[(Point2D, Intcode)]) -> HashMap<Point2D, Intcode> {
+    painted.iter().copied().collect()
+}
+
+/// Run the robot, and return each panel it paints with its new color, in the
+/// order they're painted.
+fn paint_ship<R: Renderer + ?Sized>(
     program: &[Intcode],
     start_color: Intcode,
-    draw: bool,
-) -> HashMap<Point2D, Intcode> {
-    let mut process = Process::new(&program);
+    renderer: &mut R,
+) -> io::Result<Vec<(Point2D, Intcode)>> {
+    let mut process = Process::new(program);
     let mut visited = HashMap::new();
-
-    // Drawing
-    let colors = ["black", "white"];
-
-    let mut turtle = Turtle::new();
-    if draw {
-        // turtle.drawing_mut().enter_fullscreen();
-        turtle.drawing_mut().set_background_color("grey");
-        turtle.drawing_mut().set_center((-500.0, 0.0));
-        turtle.drawing_mut().set_center((0.0, -180.0));
-        turtle.hide();
-        turtle.pen_up();
-        turtle.set_pen_size(TURTLE_SIZE / 2.0);
-        turtle.set_speed("instant");
-        turtle.use_degrees();
-    }
+    let mut painted = Vec::new();
 
     // Handling of the current direction
     let to_right = Rotation2D::new(Angle::degrees(-90.0));
@@ -129,53 +287,121 @@ fn paint_ship(
     process.input.push(start_color); // Initial color
 
     loop {
-        // Run the Intcode
-        if process.exec() == Status::Halt {
-            break;
+        // Run the Intcode, until it outputs a color and a turn
+        let chunk = process.exec_chunk().expect("Partial output");
+        let [new_color, new_dir] = match chunk {
+            Chunk::Output(chunk) => chunk,
+            Chunk::Halt => break,
+            Chunk::WantInput => panic!("Input underflow"),
+        };
+
+        // Paint current position
+        visited.insert(pos, new_color);
+        painted.push((pos, new_color));
+
+        // Turn
+        dir = match new_dir {
+            LEFT => to_left.transform_vector(dir.to_f64()).to_i32(),
+            RIGHT => to_right.transform_vector(dir.to_f64()).to_i32(),
+            _ => panic!("Unexpected direction"),
+        };
+
+        // Draw
+        renderer.paint(pos, new_color, dir)?;
+
+        // Advance
+        pos += dir;
+
+        // Add current position's color as next input
+        let color = *visited.get(&pos).unwrap_or(&BLACK);
+        process.input.push(color);
+    }
+
+    renderer.finish()?;
+
+    Ok(painted)
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program() -> Vec<Intcode> {
+        parse_program(include_str!("../input.txt"))
+    }
+
+    #[test]
+    fn options() {
+        let parse = |args: &[&str]| {
+            Options::parse(args.iter().map(|arg| arg.to_string()))
+        };
+
+        let options = parse(&["--svg", "hull.svg"]).unwrap();
+        assert_eq!(options.svg.as_deref(), Some("hull.svg"));
+        assert_eq!(options.output(), Output::Headless);
+
+        for option in &["--svg", "--stats", "--record", "--cell"] {
+            assert!(parse(&[option]).is_err());
         }
+        assert!(parse(&["--cell", "4"]).is_err());
+    }
 
-        if process.output.len() >= 2 {
-            let new_color = process.output[0];
-            let new_dir = process.output[1];
-            process.output.clear();
-
-            // Paint current position
-            visited.insert(pos, new_color);
-
-            // Turn
-            dir = match new_dir {
-                LEFT => to_left.transform_vector(dir.to_f64()).to_i32(),
-                RIGHT => to_right.transform_vector(dir.to_f64()).to_i32(),
-                _ => panic!("Unexpected direction"),
-            };
-
-            // Advance
-            pos = pos + dir;
-
-            // Draw
-            if draw {
-                turtle
-                    .set_heading(dir.to_f64().angle_from_x_axis().to_degrees());
-                turtle.backward(TURTLE_SIZE / 2.0);
-
-                turtle.set_speed(TURTLE_SPEED);
-                turtle.pen_down();
-                turtle.set_pen_color(colors[new_color as usize]);
-                turtle.forward(TURTLE_SIZE);
-                turtle.pen_up();
-                turtle.set_speed("instant");
-
-                turtle.forward(TURTLE_SIZE / 2.0);
-                turtle.set_speed(TURTLE_SPEED);
-            }
+    #[test]
+    fn part1_headless() {
+        assert_eq!(part1(&program(), &mut Headless).unwrap(), 2883);
+    }
 
-            // Add current position's color as next input
-            let color = *visited.get(&pos).unwrap_or(&BLACK);
-            process.input.push(color);
+    #[test]
+    fn part2_headless() {
+        let painted = paint_ship(&program(), WHITE, &mut Headless).unwrap();
+        assert_eq!(part2(&painted), Ok("LEPCPLGZ".to_string()));
+    }
+
+    #[test]
+    fn stats() {
+        let mut stats = Stats::new();
+        let answer = part1(&program(), &mut stats).unwrap();
+        assert_eq!(stats.panels.len(), answer);
+
+        // One step per panel painted, from where the robot started
+        let paints: usize =
+            stats.paint_counts().iter().map(|(n, p)| n * p).sum();
+        assert_eq!(stats.path.len(), paints + 1);
+        assert_eq!(stats.panels[&stats.path[0]].first_step, 0);
+        for (pos, panel) in &stats.panels {
+            assert_eq!(stats.path[panel.first_step], *pos);
         }
     }
 
-    visited
+    #[test]
+    fn renderers_follow_the_robot() {
+        // Counts panels painted, and checks the robot always moves on
+        struct Counter(usize, bool);
+        impl Renderer for Counter {
+            fn paint(
+                &mut self,
+                _: Point2D,
+                _: Intcode,
+                dir: Vector2D,
+            ) -> io::Result<()> {
+                assert_eq!(dir.x.abs() + dir.y.abs(), 1);
+                self.0 += 1;
+                Ok(())
+            }
+
+            fn finish(&mut self) -> io::Result<()> {
+                self.1 = true;
+                Ok(())
+            }
+        }
+
+        let mut counter = Counter(0, false);
+        let painted = paint_ship(&program(), WHITE, &mut counter).unwrap();
+        assert_eq!(counter.0, painted.len());
+        assert!(counter.1);
+    }
 }
 
 // ----------------------------------------------------------------------------
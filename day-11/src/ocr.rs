@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::intcode::Intcode;
+use crate::{Point2D, WHITE};
+
+// Reading of the capital letters the puzzles draw, in a 4x6 font with a blank
+// column between letters.
+//
+// Letters are read on a 5-column stride, from the first lit column or up to
+// a few columns before it, since some letters start with blank columns; the
+// alignment with the fewest unknown glyphs wins.
+
+const WIDTH: usize = 4;
+const HEIGHT: usize = 6;
+const STRIDE: usize = WIDTH + 1;
+
+/// Known letters, by bitmap rows.
+const FONT: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// A glyph that isn't in the font.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    /// Position of the glyph in the text.
+    pub index: usize,
+    /// Rows of `#` and `.`, one per line.
+    pub bitmap: String,
+}
+
+/// Text with some unknown glyphs, shown as `?`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unreadable {
+    pub text: String,
+    pub glyphs: Vec<Glyph>,
+}
+
+// ----------------------------------------------------------------------------
+
+/// Read the text drawn with lit cells, by row.
+pub fn read(rows: &[Vec<bool>]) -> Result<String, Unreadable> {
+    let lit = |x: usize| rows.iter().any(|row| row.get(x) == Some(&true));
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let first_x = match (0..width).find(|x| lit(*x)) {
+        Some(x) => x,
+        None => return Ok(String::new()),
+    };
+    let first_y = rows.iter().position(|row| row.contains(&true)).unwrap_or(0);
+    let last_x = (0..width).rev().find(|x| lit(*x)).unwrap_or(0);
+
+    (0..WIDTH.min(first_x + 1))
+        .map(|shift| read_from(rows, first_x - shift, last_x, first_y))
+        .min_by_key(|result| {
+            result.as_ref().map_or_else(|e| e.glyphs.len(), |_| 0)
+        })
+        .expect("alignment")
+}
+
+/// Read the text drawn with white panels.
+pub fn read_panels(
+    panels: &HashMap<Point2D, Intcode>,
+) -> Result<String, Unreadable> {
+    let white: Vec<Point2D> = panels
+        .iter()
+        .filter(|(_, color)| **color == WHITE)
+        .map(|(pos, _)| *pos)
+        .collect();
+    let min_x = white.iter().map(|p| p.x).min().unwrap_or(0);
+    let max_x = white.iter().map(|p| p.x).max().unwrap_or(0);
+    let max_y = white.iter().map(|p| p.y).max().unwrap_or(0);
+    let min_y = white.iter().map(|p| p.y).min().unwrap_or(0);
+
+    // Rows go down, so the y axis is flipped
+    let mut rows = vec![
+        vec![false; (max_x - min_x + 1) as usize];
+        (max_y - min_y + 1) as usize
+    ];
+    for pos in white {
+        rows[(max_y - pos.y) as usize][(pos.x - min_x) as usize] = true;
+    }
+
+    read(&rows)
+}
+
+/// Read glyphs with their top left corner at `(x, y)`, then every `STRIDE`
+/// columns up to `last_x`.
+fn read_from(
+    rows: &[Vec<bool>],
+    x: usize,
+    last_x: usize,
+    y: usize,
+) -> Result<String, Unreadable> {
+    let mut text = String::new();
+    let mut glyphs = Vec::new();
+
+    for (index, left) in (x..=last_x).step_by(STRIDE).enumerate() {
+        let bitmap: Vec<String> = (y..y + HEIGHT)
+            .map(|y| {
+                (left..left + WIDTH)
+                    .map(|x| {
+                        let cell = rows.get(y).and_then(|row| row.get(x));
+                        if cell == Some(&true) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let letter = FONT
+            .iter()
+            .find(|(_, rows)| rows.iter().zip(&bitmap).all(|(a, b)| a == b));
+        match letter {
+            Some((letter, _)) => text.push(*letter),
+            None => {
+                text.push('?');
+                glyphs.push(Glyph {
+                    index,
+                    bitmap: bitmap.join("\n"),
+                });
+            }
+        }
+    }
+
+    if glyphs.is_empty() {
+        Ok(text)
+    } else {
+        Err(Unreadable { text, glyphs })
+    }
+}
+
+impl fmt::Display for Unreadable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Unknown glyphs in {:?}", self.text)?;
+        for glyph in &self.glyphs {
+            writeln!(f, "#{}:\n{}", glyph.index + 1, glyph.bitmap)?;
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse rows of `#` and anything else.
+    fn grid(text: &str) -> Vec<Vec<bool>> {
+        text.lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    #[test]
+    fn letters() {
+        let rows = grid(
+            "
+ #### #  #  ##  ###  #### ####
+ #    #  # #  # #  # #       #
+ ###  #### #  # #  # ###    #
+ #    #  # #  # ###  #     #
+ #    #  # #  # #    #    #
+ #### #  #  ##  #    #### #### ",
+        );
+        assert_eq!(read(&rows), Ok("EHOPEZ".to_string()));
+
+        // Starting with a blank column
+        let rows = grid(
+            "
+ ###   ##
+  #     #
+  #     #
+  #     #
+  #  #  #
+ ###  ## ",
+        );
+        assert_eq!(read(&rows), Ok("IJ".to_string()));
+
+        assert_eq!(read(&[]), Ok(String::new()));
+    }
+
+    #[test]
+    fn unknown_glyphs() {
+        let rows = grid(
+            "
+#  # #
+#  # ##
+#### # #
+#  # #  #
+#  # #
+#  # #   ",
+        );
+        let err = read(&rows).unwrap_err();
+        assert_eq!(err.text, "H?");
+        assert_eq!(
+            err.glyphs,
+            vec![Glyph {
+                index: 1,
+                bitmap: "#...\n##..\n#.#.\n#..#\n#...\n#...".to_string(),
+            }]
+        );
+    }
+}
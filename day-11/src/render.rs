@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use recorder::{Frame, Recorder};
+
+use crate::intcode::Intcode;
+use crate::{Point2D, Vector2D, WHITE};
+
+// Renderers follow the robot as it paints the hull, one panel at a time. The
+// robot itself runs with none, and several can follow it at once.
+
+pub trait Renderer {
+    /// Called once the robot painted the panel at `pos` with `color`, and
+    /// turned to face `dir`, before it moves on.
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        dir: Vector2D,
+    ) -> io::Result<()>;
+
+    /// Called once the robot is done.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Draws nothing, for quick puzzle answers and tests.
+pub struct Headless;
+
+/// Draws in a window, with a turtle walking along with the robot.
+/// `turtle::start()` must be called first thing in `main`.
+pub struct TurtleWindow {
+    turtle: turtle::Turtle,
+}
+
+/// Draws on the terminal with ANSI escape codes, two columns per panel.
+pub struct Ansi {
+    out: io::Stdout,
+    panels: HashMap<Point2D, Intcode>,
+    robot: Point2D,
+    /// Panels drawn, as `(min_x, max_x, min_y, max_y)`.
+    bounds: Option<(i32, i32, i32, i32)>,
+    delay: Duration,
+}
+
+/// Saves the final hull as an SVG image, with the path of the robot on top.
+pub struct Svg {
+    path: String,
+    painted: Vec<(Point2D, Intcode)>,
+}
+
+/// Records a frame for each panel painted, with the robot on it, and saves
+/// them all once the robot is done.
+pub struct Recording {
+    recorder: Recorder,
+    path: String,
+    painted: Vec<(Point2D, Intcode)>,
+}
+
+// Config
+
+const TURTLE_SIZE: f64 = 5.0;
+// const TURTLE_SPEED: i32 = 8;  // Range: [1, 25]
+const TURTLE_SPEED: &str = "instant";
+
+const SVG_CELL: i32 = 10; // Side of each panel, in pixels
+
+// ----------------------------------------------------------------------------
+
+impl Renderer for Headless {
+    fn paint(&mut self, _: Point2D, _: Intcode, _: Vector2D) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renderers borrowed for a while, to look at once the robot is done.
+impl<R: Renderer + ?Sized> Renderer for &mut R {
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        dir: Vector2D,
+    ) -> io::Result<()> {
+        (**self).paint(pos, color, dir)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// Several renderers following the robot at once.
+impl<'a> Renderer for Vec<Box<dyn Renderer + 'a>> {
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        dir: Vector2D,
+    ) -> io::Result<()> {
+        self.iter_mut()
+            .try_for_each(|renderer| renderer.paint(pos, color, dir))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.iter_mut().try_for_each(|renderer| renderer.finish())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl TurtleWindow {
+    pub fn new() -> TurtleWindow {
+        let mut turtle = turtle::Turtle::new();
+
+        // turtle.drawing_mut().enter_fullscreen();
+        turtle.drawing_mut().set_background_color("grey");
+        turtle.drawing_mut().set_center((0.0, -180.0));
+        turtle.hide();
+        turtle.pen_up();
+        turtle.set_pen_size(TURTLE_SIZE / 2.0);
+        turtle.set_speed("instant");
+        turtle.use_degrees();
+
+        TurtleWindow { turtle }
+    }
+}
+
+impl Renderer for TurtleWindow {
+    fn paint(
+        &mut self,
+        _pos: Point2D,
+        color: Intcode,
+        dir: Vector2D,
+    ) -> io::Result<()> {
+        let colors = ["black", "white"];
+        let turtle = &mut self.turtle;
+
+        // The turtle is on the panel, and draws across it towards the next
+        turtle.set_heading(dir.to_f64().angle_from_x_axis().to_degrees());
+        turtle.backward(TURTLE_SIZE / 2.0);
+
+        turtle.set_speed(TURTLE_SPEED);
+        turtle.pen_down();
+        turtle.set_pen_color(colors[color as usize]);
+        turtle.forward(TURTLE_SIZE);
+        turtle.pen_up();
+        turtle.forward(TURTLE_SIZE / 2.0);
+
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Ansi {
+    /// Clear the terminal, and wait `delay` after each panel painted.
+    pub fn new(delay: Duration) -> io::Result<Ansi> {
+        let mut out = io::stdout();
+        out.write_all(b"\x1b[2J\x1b[?25l")?;
+
+        Ok(Ansi {
+            out,
+            panels: HashMap::new(),
+            robot: Point2D::new(0, 0),
+            bounds: None,
+            delay,
+        })
+    }
+
+    fn cell(&self, pos: Point2D) -> &'static str {
+        if pos == self.robot {
+            return "\x1b[41m  \x1b[0m";
+        }
+        match self.panels.get(&pos) {
+            Some(&WHITE) => "\x1b[47m  \x1b[0m",
+            Some(_) => "\x1b[40m  \x1b[0m",
+            None => "  ",
+        }
+    }
+
+    /// Draw a single panel, if the whole hull needs no redrawing.
+    fn draw_cell(&mut self, pos: Point2D) -> io::Result<()> {
+        let (min_x, _, _, max_y) = self.bounds.expect("bounds");
+        let row = max_y - pos.y + 1;
+        let column = (pos.x - min_x) * 2 + 1;
+        write!(self.out, "\x1b[{};{}H{}", row, column, self.cell(pos))
+    }
+
+    fn draw_all(&mut self) -> io::Result<()> {
+        let (min_x, max_x, min_y, max_y) = self.bounds.expect("bounds");
+
+        let mut text = String::from("\x1b[H");
+        for y in (min_y..=max_y).rev() {
+            for x in min_x..=max_x {
+                text.push_str(self.cell(Point2D::new(x, y)));
+            }
+            text.push_str("\x1b[K\n");
+        }
+        self.out.write_all(text.as_bytes())
+    }
+}
+
+impl Renderer for Ansi {
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        dir: Vector2D,
+    ) -> io::Result<()> {
+        self.panels.insert(pos, color);
+        self.robot = pos + dir;
+
+        let robot = self.robot;
+        let bounds = match self.bounds {
+            Some((min_x, max_x, min_y, max_y)) => (
+                min_x.min(robot.x),
+                max_x.max(robot.x),
+                min_y.min(robot.y),
+                max_y.max(robot.y),
+            ),
+            None => (
+                pos.x.min(robot.x),
+                pos.x.max(robot.x),
+                pos.y.min(robot.y),
+                pos.y.max(robot.y),
+            ),
+        };
+
+        if self.bounds == Some(bounds) {
+            self.draw_cell(pos)?;
+            self.draw_cell(robot)?;
+        } else {
+            self.bounds = Some(bounds);
+            self.draw_all()?;
+        }
+        self.out.flush()?;
+
+        thread::sleep(self.delay);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some((_, _, min_y, max_y)) = self.bounds {
+            write!(self.out, "\x1b[{};1H", max_y - min_y + 2)?;
+        }
+        self.out.write_all(b"\x1b[?25h")?;
+        self.out.flush()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Svg {
+    pub fn new(path: &str) -> Svg {
+        Svg {
+            path: path.to_string(),
+            painted: Vec::new(),
+        }
+    }
+}
+
+impl Renderer for Svg {
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        _: Vector2D,
+    ) -> io::Result<()> {
+        self.painted.push((pos, color));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        fs::write(&self.path, svg(&self.painted))
+    }
+}
+
+/// SVG image of painted panels, with the robot's path through them.
+fn svg(painted: &[(Point2D, Intcode)]) -> String {
+    let (min_x, max_x, min_y, max_y) = bounds(painted);
+    let width = (max_x - min_x + 1) * SVG_CELL;
+    let height = (max_y - min_y + 1) * SVG_CELL;
+
+    // Rows go down, so the y axis is flipped
+    let corner =
+        |pos: Point2D| ((pos.x - min_x) * SVG_CELL, (max_y - pos.y) * SVG_CELL);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+        width, height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="grey"/>"#,
+        width, height
+    )
+    .unwrap();
+
+    let panels: HashMap<Point2D, Intcode> = painted.iter().copied().collect();
+    let mut panels: Vec<_> = panels.into_iter().collect();
+    panels.sort_by_key(|(pos, _)| (-pos.y, pos.x));
+    for (pos, color) in panels {
+        let (x, y) = corner(pos);
+        let fill = if color == WHITE { "white" } else { "black" };
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            x, y, SVG_CELL, SVG_CELL, fill
+        )
+        .unwrap();
+    }
+
+    let points: Vec<String> = painted
+        .iter()
+        .map(|(pos, _)| {
+            let (x, y) = corner(*pos);
+            format!("{},{}", x + SVG_CELL / 2, y + SVG_CELL / 2)
+        })
+        .collect();
+    writeln!(
+        svg,
+        r#"<polyline points="{}" fill="none" stroke="red" stroke-width="1" stroke-opacity="0.5"/>"#,
+        points.join(" ")
+    )
+    .unwrap();
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Bounds of painted panels, as `(min_x, max_x, min_y, max_y)`.
+fn bounds(painted: &[(Point2D, Intcode)]) -> (i32, i32, i32, i32) {
+    let min_x = painted.iter().map(|(p, _)| p.x).min().unwrap_or(0);
+    let max_x = painted.iter().map(|(p, _)| p.x).max().unwrap_or(0);
+    let min_y = painted.iter().map(|(p, _)| p.y).min().unwrap_or(0);
+    let max_y = painted.iter().map(|(p, _)| p.y).max().unwrap_or(0);
+    (min_x, max_x, min_y, max_y)
+}
+
+// ----------------------------------------------------------------------------
+
+impl Recording {
+    pub fn new(recorder: Recorder, path: &str) -> Recording {
+        Recording {
+            recorder,
+            path: path.to_string(),
+            painted: Vec::new(),
+        }
+    }
+}
+
+impl Renderer for Recording {
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        _: Vector2D,
+    ) -> io::Result<()> {
+        self.painted.push((pos, color));
+        Ok(())
+    }
+
+    /// Frames are only made once the robot is done, and the size of the hull
+    /// painted is known.
+    fn finish(&mut self) -> io::Result<()> {
+        let (min_x, max_x, min_y, max_y) = bounds(&self.painted);
+
+        // Rows go down, so the y axis is flipped
+        let cell =
+            |pos: Point2D| ((pos.x - min_x) as usize, (max_y - pos.y) as usize);
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+
+        let mut hull = Frame::new(width, height);
+        for (pos, color) in &self.painted {
+            let (x, y) = cell(*pos);
+            hull.set(x, y, *color as u8 + 1);
+
+            let mut frame = hull.clone();
+            frame.set(x, y, 3);
+            self.recorder.push(frame);
+        }
+        self.recorder.push(hull);
+
+        self.recorder.save(&self.path)?;
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLACK;
+    use recorder::Palette;
+    use std::env;
+
+    /// An L-shaped path over three panels, turning left at the second one.
+    fn painted() -> Vec<(Point2D, Intcode)> {
+        vec![
+            (Point2D::new(0, 0), WHITE),
+            (Point2D::new(1, 0), BLACK),
+            (Point2D::new(1, 1), WHITE),
+        ]
+    }
+
+    #[test]
+    fn svg_panels_and_path() {
+        let svg = svg(&painted());
+        let lines: Vec<&str> = svg.lines().collect();
+
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].contains(r#"width="20" height="20""#));
+        assert_eq!(
+            &lines[2..5],
+            [
+                r#"<rect x="10" y="0" width="10" height="10" fill="white"/>"#,
+                r#"<rect x="0" y="10" width="10" height="10" fill="white"/>"#,
+                r#"<rect x="10" y="10" width="10" height="10" fill="black"/>"#,
+            ]
+        );
+        assert!(lines[5].starts_with(r#"<polyline points="5,15 15,15 15,5""#));
+        assert_eq!(lines[6], "</svg>");
+    }
+
+    #[test]
+    fn recording_frames() {
+        let palette = Palette::parse("808080,000000,ffffff,ff0000").unwrap();
+        let path = env::temp_dir().join("day-11-recording-test.gif");
+        let mut recording =
+            Recording::new(Recorder::new(palette), path.to_str().unwrap());
+        for (pos, color) in painted() {
+            recording.paint(pos, color, Vector2D::new(0, 1)).unwrap();
+        }
+        recording.finish().unwrap();
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+
+        // One frame per panel painted, with the robot on it, and the hull
+        let frames = recording.recorder.frames();
+        assert_eq!(frames.len(), 4);
+        assert_eq!((frames[0].get(0, 1), frames[0].get(1, 1)), (3, 0));
+        assert_eq!((frames[1].get(0, 1), frames[1].get(1, 1)), (2, 3));
+
+        let hull = &frames[3];
+        let rows: Vec<Vec<u8>> = (0..2)
+            .map(|y| (0..2).map(|x| hull.get(x, y)).collect())
+            .collect();
+        assert_eq!(rows, [[0, 2], [2, 1]]);
+    }
+}
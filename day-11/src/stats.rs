@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use recorder::{Color, Frame, Palette, Recorder};
+
+use crate::intcode::Intcode;
+use crate::render::Renderer;
+use crate::{Point2D, Vector2D, WHITE};
+
+// Statistics of the robot painting the hull: how often each panel was
+// painted, with what colors, and when it was first visited, along with the
+// robot's whole path. They're exported as CSV, and as a heat map of the
+// panels by number of times painted, from dark blue for once to yellow for
+// the most, with panels never painted in grey.
+
+/// Shades in the heat map gradient.
+const SHADES: usize = 16;
+
+/// Painting history of a single panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Panel {
+    /// Step of the robot's first visit.
+    pub first_step: usize,
+    /// Colors painted, in order.
+    pub colors: Vec<Intcode>,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub panels: HashMap<Point2D, Panel>,
+    /// Position of the robot at each step, ending where it stopped.
+    pub path: Vec<Point2D>,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Stats {
+    pub fn new() -> Stats {
+        Default::default()
+    }
+
+    /// Number of panels painted each number of times.
+    pub fn paint_counts(&self) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        for panel in self.panels.values() {
+            *counts.entry(panel.colors.len()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Panels sorted by first visit.
+    fn sorted(&self) -> Vec<(&Point2D, &Panel)> {
+        let mut panels: Vec<_> = self.panels.iter().collect();
+        panels.sort_by_key(|(_, panel)| panel.first_step);
+        panels
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// One row per panel painted, by first visit, with its colors in order.
+    pub fn panels_csv(&self) -> String {
+        let mut csv = String::from("x,y,first_step,paints,colors\n");
+        for (pos, panel) in self.sorted() {
+            let colors: Vec<String> =
+                panel.colors.iter().map(|c| c.to_string()).collect();
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                pos.x,
+                pos.y,
+                panel.first_step,
+                panel.colors.len(),
+                colors.join(";")
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    pub fn path_csv(&self) -> String {
+        let mut csv = String::from("step,x,y\n");
+        for (step, pos) in self.path.iter().enumerate() {
+            writeln!(csv, "{},{},{}", step, pos.x, pos.y).unwrap();
+        }
+        csv
+    }
+
+    /// Heat map of the panels, as a recording with a single frame.
+    pub fn heat_map(&self, cell_size: usize) -> Recorder {
+        let mut colors = vec![[0x60, 0x60, 0x60]];
+        colors.extend(gradient([0x10, 0x10, 0x60], [0xff, 0xe0, 0x20]));
+
+        let min_x = self.path.iter().map(|p| p.x).min().unwrap_or(0);
+        let max_x = self.path.iter().map(|p| p.x).max().unwrap_or(0);
+        let min_y = self.path.iter().map(|p| p.y).min().unwrap_or(0);
+        let max_y = self.path.iter().map(|p| p.y).max().unwrap_or(0);
+
+        // Rows go down, so the y axis is flipped
+        let mut frame = Frame::new(
+            (max_x - min_x + 1) as usize,
+            (max_y - min_y + 1) as usize,
+        );
+        let most = self.panels.values().map(|p| p.colors.len()).max();
+        for (pos, panel) in &self.panels {
+            let shade = (panel.colors.len() - 1) * (SHADES - 1)
+                / (most.unwrap_or(1) - 1).max(1);
+            let (x, y) = ((pos.x - min_x) as usize, (max_y - pos.y) as usize);
+            frame.set(x, y, 1 + shade as u8);
+        }
+
+        let mut recorder = Recorder::new(Palette(colors)).cell_size(cell_size);
+        recorder.push(frame);
+        recorder
+    }
+
+    /// Write all exports to `dir`, creating it if needed, with the heat map
+    /// scaled up by `cell_size`.
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        cell_size: usize,
+    ) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("panels.csv"), self.panels_csv())?;
+        fs::write(dir.join("path.csv"), self.path_csv())?;
+
+        let mut png = fs::File::create(dir.join("heat-map.png"))?;
+        self.heat_map(cell_size).write_png(0, &mut png)
+    }
+}
+
+/// Colors from `from` to `to`, in `SHADES` steps.
+fn gradient(from: Color, to: Color) -> Vec<Color> {
+    (0..SHADES)
+        .map(|i| {
+            let mix = |a: u8, b: u8| {
+                let (a, b) = (a as usize, b as usize);
+                ((a * (SHADES - 1 - i) + b * i) / (SHADES - 1)) as u8
+            };
+            [
+                mix(from[0], to[0]),
+                mix(from[1], to[1]),
+                mix(from[2], to[2]),
+            ]
+        })
+        .collect()
+}
+
+impl Renderer for Stats {
+    fn paint(
+        &mut self,
+        pos: Point2D,
+        color: Intcode,
+        dir: Vector2D,
+    ) -> io::Result<()> {
+        if self.path.is_empty() {
+            self.path.push(pos);
+        }
+        let step = self.path.len() - 1;
+
+        let panel = self.panels.entry(pos).or_insert(Panel {
+            first_step: step,
+            colors: Vec::new(),
+        });
+        panel.colors.push(color);
+
+        self.path.push(pos + dir);
+        Ok(())
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let white = self
+            .panels
+            .values()
+            .filter(|panel| panel.colors.last() == Some(&WHITE))
+            .count();
+        writeln!(
+            f,
+            "Steps: {}, panels painted: {} ({} white in the end)",
+            self.path.len().saturating_sub(1),
+            self.panels.len(),
+            white
+        )?;
+
+        let counts: Vec<String> = self
+            .paint_counts()
+            .iter()
+            .map(|(paints, panels)| format!("{}x: {}", paints, panels))
+            .collect();
+        writeln!(f, "Panels by times painted: {}", counts.join(", "))?;
+
+        // Panels painted again with the color they already had
+        let repaints: usize = self
+            .panels
+            .values()
+            .map(|p| p.colors.windows(2).filter(|w| w[0] == w[1]).count())
+            .sum();
+        writeln!(f, "Repaints with the same color: {}", repaints)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLACK;
+
+    /// Around a square of four panels, painting the first one twice.
+    fn stats() -> Stats {
+        let mut stats = Stats::new();
+        let (up, down) = (Vector2D::new(0, 1), Vector2D::new(0, -1));
+        let (left, right) = (Vector2D::new(-1, 0), Vector2D::new(1, 0));
+        let paints = [
+            ((0, 0), WHITE, up),
+            ((0, 1), BLACK, right),
+            ((1, 1), WHITE, down),
+            ((1, 0), WHITE, left),
+            ((0, 0), WHITE, up),
+        ];
+        for ((x, y), color, dir) in paints.iter() {
+            stats.paint(Point2D::new(*x, *y), *color, *dir).unwrap();
+        }
+        stats
+    }
+
+    #[test]
+    fn csv() {
+        let stats = stats();
+        assert_eq!(
+            stats.panels_csv(),
+            "x,y,first_step,paints,colors\n\
+             0,0,0,2,1;1\n\
+             0,1,1,1,0\n\
+             1,1,2,1,1\n\
+             1,0,3,1,1\n"
+        );
+        assert_eq!(
+            stats.path_csv(),
+            "step,x,y\n0,0,0\n1,0,1\n2,1,1\n3,1,0\n4,0,0\n5,0,1\n"
+        );
+    }
+
+    #[test]
+    fn heat_map() {
+        let recorder = stats().heat_map(1);
+        let frame = &recorder.frames()[0];
+
+        // Painted once in the first shade, and twice, the most, in the last
+        assert_eq!((frame.get(0, 0), frame.get(1, 0)), (1, 1));
+        assert_eq!((frame.get(0, 1), frame.get(1, 1)), (SHADES as u8, 1));
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(
+            stats().to_string(),
+            "Steps: 5, panels painted: 4 (3 white in the end)\n\
+             Panels by times painted: 1x: 3, 2x: 1\n\
+             Repaints with the same color: 1\n"
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use recorder::{Color, Frame, Palette, Recorder};
+
+use crate::intcode::Intcode;
+use crate::render::Renderer;
+use crate::screen::{Point2D, Screen, Tile, Update};
+
+// Analytics of a game run: which blocks were broken, when, and for how many
+// points, how the score went up, where the ball went, and where the paddle
+// spent its time. They're collected by following the renderer calls, and
+// exported as CSV, JSON, and a summary image:
+//
+// * Blocks are colored by the order they were broken in, from red to blue,
+//   with any left over in white.
+// * The ball's path is shaded in yellow, brighter where it went more often.
+// * The paddle's row is shaded in green, by the time it spent at each spot.
+
+/// Shades in each gradient of the summary image.
+const SHADES: usize = 16;
+
+/// A block broken by the ball.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub frame: usize,
+    pub pos: Point2D,
+    /// Points scored for it, if the score went up right after.
+    pub points: Option<Intcode>,
+}
+
+#[derive(Debug, Default)]
+pub struct Analytics {
+    /// Number of frames drawn.
+    pub frames: usize,
+    /// Blocks broken, in order.
+    pub hits: Vec<Hit>,
+    /// Score each time it changed, by frame.
+    pub scores: Vec<(usize, Intcode)>,
+    /// Where the ball was on each frame.
+    pub ball: Vec<(usize, Point2D)>,
+    /// Number of frames with the paddle at each column.
+    pub paddle: BTreeMap<Intcode, usize>,
+    paddle_y: Option<Intcode>,
+    walls: Vec<Point2D>,
+    blocks: HashSet<Point2D>,
+    size: (usize, usize),
+}
+
+/// Collects analytics while passing everything on to another renderer, and
+/// saves them once the game is over.
+pub struct Analysis<R: Renderer> {
+    inner: R,
+    pub analytics: Analytics,
+    dir: Option<PathBuf>,
+    cell_size: usize,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Analytics {
+    fn update(&mut self, screen: &Screen, update: Update) {
+        self.size = (screen.width(), screen.height());
+
+        match update {
+            Update::Tile(pos, Tile::Wall) => self.walls.push(pos),
+            Update::Tile(pos, Tile::Block) => {
+                self.blocks.insert(pos);
+            }
+            Update::Tile(pos, _) => {
+                if self.blocks.remove(&pos) {
+                    self.hits.push(Hit {
+                        frame: self.frames,
+                        pos,
+                        points: None,
+                    });
+                }
+            }
+            Update::Score(score) => {
+                let last = self.scores.last().map_or(0, |(_, score)| *score);
+                match self.hits.last_mut() {
+                    Some(hit) if hit.points.is_none() && score > last => {
+                        hit.points = Some(score - last)
+                    }
+                    _ => (),
+                }
+                self.scores.push((self.frames, score));
+            }
+        }
+    }
+
+    fn frame(&mut self, screen: &Screen) {
+        if let Some(ball) = screen.ball() {
+            self.ball.push((self.frames, ball));
+        }
+        if let Some(paddle) = screen.paddle() {
+            *self.paddle.entry(paddle.x).or_insert(0) += 1;
+            self.paddle_y = Some(paddle.y);
+        }
+        self.frames += 1;
+    }
+
+    /// Ball trajectory, down to the frames where it changed direction, along
+    /// with the first and last ones.
+    pub fn polyline(&self) -> Vec<(usize, Point2D)> {
+        let direction = |(_, a): (usize, Point2D), (_, b): (usize, Point2D)| {
+            (b.x - a.x, b.y - a.y)
+        };
+
+        let mut points = Vec::new();
+        for (i, point) in self.ball.iter().enumerate() {
+            let turns = match (i.checked_sub(1), self.ball.get(i + 1)) {
+                (Some(prev), Some(next)) => {
+                    direction(self.ball[prev], *point)
+                        != direction(*point, *next)
+                }
+                _ => true,
+            };
+            if turns {
+                points.push(*point);
+            }
+        }
+        points
+    }
+
+    // ------------------------------------------------------------------------
+
+    pub fn hits_csv(&self) -> String {
+        let mut csv = String::from("order,frame,x,y,points\n");
+        for (i, hit) in self.hits.iter().enumerate() {
+            let points = hit.points.map_or(String::new(), |p| p.to_string());
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                i + 1,
+                hit.frame,
+                hit.pos.x,
+                hit.pos.y,
+                points
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    pub fn scores_csv(&self) -> String {
+        let mut csv = String::from("frame,score\n");
+        for (frame, score) in &self.scores {
+            writeln!(csv, "{},{}", frame, score).unwrap();
+        }
+        csv
+    }
+
+    /// Vertices of the ball trajectory.
+    pub fn ball_csv(&self) -> String {
+        let mut csv = String::from("frame,x,y\n");
+        for (frame, pos) in self.polyline() {
+            writeln!(csv, "{},{},{}", frame, pos.x, pos.y).unwrap();
+        }
+        csv
+    }
+
+    pub fn paddle_csv(&self) -> String {
+        let mut csv = String::from("x,frames\n");
+        for (x, frames) in &self.paddle {
+            writeln!(csv, "{},{}", x, frames).unwrap();
+        }
+        csv
+    }
+
+    /// All of the above in a single document.
+    pub fn to_json(&self) -> String {
+        let hits: Vec<String> = self
+            .hits
+            .iter()
+            .map(|hit| {
+                let points =
+                    hit.points.map_or("null".to_string(), |p| p.to_string());
+                format!(
+                    r#"{{"frame": {}, "x": {}, "y": {}, "points": {}}}"#,
+                    hit.frame, hit.pos.x, hit.pos.y, points
+                )
+            })
+            .collect();
+        let scores: Vec<String> = self
+            .scores
+            .iter()
+            .map(|(frame, score)| {
+                format!(r#"{{"frame": {}, "score": {}}}"#, frame, score)
+            })
+            .collect();
+        let ball: Vec<String> = self
+            .polyline()
+            .iter()
+            .map(|(frame, pos)| {
+                format!(
+                    r#"{{"frame": {}, "x": {}, "y": {}}}"#,
+                    frame, pos.x, pos.y
+                )
+            })
+            .collect();
+        let paddle: Vec<String> = self
+            .paddle
+            .iter()
+            .map(|(x, frames)| {
+                format!(r#"{{"x": {}, "frames": {}}}"#, x, frames)
+            })
+            .collect();
+
+        let list = |items: Vec<String>| {
+            if items.is_empty() {
+                "[]".to_string()
+            } else {
+                format!("[\n    {}\n  ]", items.join(",\n    "))
+            }
+        };
+
+        let mut json = String::from("{\n");
+        writeln!(json, r#"  "frames": {},"#, self.frames).unwrap();
+        writeln!(json, r#"  "blocks_left": {},"#, self.blocks.len()).unwrap();
+        writeln!(json, r#"  "hits": {},"#, list(hits)).unwrap();
+        writeln!(json, r#"  "scores": {},"#, list(scores)).unwrap();
+        writeln!(json, r#"  "ball": {},"#, list(ball)).unwrap();
+        writeln!(json, r#"  "paddle": {}"#, list(paddle)).unwrap();
+        json.push_str("}\n");
+        json
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Summary image of the game, as a recording with a single frame.
+    pub fn summary(&self, cell_size: usize) -> Recorder {
+        // Background, walls, blocks left, then each gradient
+        let blocks = 3;
+        let ball = blocks + SHADES;
+        let paddle = ball + SHADES;
+
+        let mut colors = vec![[0, 0, 0], [0x80, 0x80, 0x80], [0xff; 3]];
+        colors.extend(gradient([0xff, 0x20, 0x20], [0x20, 0x40, 0xff]));
+        colors.extend(gradient([0x30, 0x30, 0x00], [0xff, 0xff, 0x40]));
+        colors.extend(gradient([0x00, 0x30, 0x00], [0x40, 0xff, 0x40]));
+
+        let (width, height) = self.size;
+        let mut frame = Frame::new(width, height);
+        let mut set = |pos: Point2D, value: usize| {
+            frame.set(pos.x as usize, pos.y as usize, value as u8)
+        };
+
+        let mut visits = BTreeMap::new();
+        for (_, pos) in &self.ball {
+            *visits.entry((pos.x, pos.y)).or_insert(0) += 1;
+        }
+        let most = visits.values().copied().max().unwrap_or(0);
+        for ((x, y), count) in &visits {
+            set(Point2D { x: *x, y: *y }, ball + shade(*count, most));
+        }
+
+        if let Some(y) = self.paddle_y {
+            let most = self.paddle.values().copied().max().unwrap_or(0);
+            for (x, frames) in &self.paddle {
+                set(Point2D { x: *x, y }, paddle + shade(*frames, most));
+            }
+        }
+
+        for (i, hit) in self.hits.iter().enumerate() {
+            set(hit.pos, blocks + i * SHADES / self.hits.len());
+        }
+        for pos in &self.blocks {
+            set(*pos, 2);
+        }
+        for pos in &self.walls {
+            set(*pos, 1);
+        }
+
+        let mut recorder = Recorder::new(Palette(colors)).cell_size(cell_size);
+        recorder.push(frame);
+        recorder
+    }
+
+    /// Write all exports to `dir`, creating it if needed.
+    pub fn save(&self, dir: &Path, cell_size: usize) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("hits.csv"), self.hits_csv())?;
+        fs::write(dir.join("scores.csv"), self.scores_csv())?;
+        fs::write(dir.join("ball.csv"), self.ball_csv())?;
+        fs::write(dir.join("paddle.csv"), self.paddle_csv())?;
+        fs::write(dir.join("analytics.json"), self.to_json())?;
+
+        let mut png = fs::File::create(dir.join("summary.png"))?;
+        self.summary(cell_size).write_png(0, &mut png)
+    }
+}
+
+/// Colors from `from` to `to`, in `SHADES` steps.
+fn gradient(from: Color, to: Color) -> Vec<Color> {
+    (0..SHADES)
+        .map(|i| {
+            let mix = |a: u8, b: u8| {
+                let (a, b) = (a as usize, b as usize);
+                ((a * (SHADES - 1 - i) + b * i) / (SHADES - 1)) as u8
+            };
+            [
+                mix(from[0], to[0]),
+                mix(from[1], to[1]),
+                mix(from[2], to[2]),
+            ]
+        })
+        .collect()
+}
+
+/// Shade for a count out of `most`, on a logarithmic scale.
+fn shade(count: usize, most: usize) -> usize {
+    if most <= 1 {
+        return SHADES - 1;
+    }
+    let level = (count as f64).ln() / (most as f64).ln();
+    (level * (SHADES - 1) as f64).round() as usize
+}
+
+// ----------------------------------------------------------------------------
+
+impl<R: Renderer> Analysis<R> {
+    pub fn new(inner: R) -> Analysis<R> {
+        Analysis {
+            inner,
+            analytics: Analytics::default(),
+            dir: None,
+            cell_size: 4,
+        }
+    }
+
+    /// Save the analytics to `dir` once the game is over, with the summary
+    /// image scaled up by `cell_size`.
+    pub fn save_to<P: AsRef<Path>>(
+        mut self,
+        dir: P,
+        cell_size: usize,
+    ) -> Analysis<R> {
+        self.dir = Some(dir.as_ref().to_path_buf());
+        self.cell_size = cell_size;
+        self
+    }
+}
+
+impl<R: Renderer> Renderer for Analysis<R> {
+    fn update(&mut self, screen: &Screen, update: Update) -> io::Result<()> {
+        self.analytics.update(screen, update);
+        self.inner.update(screen, update)
+    }
+
+    fn message(
+        &mut self,
+        screen: &Screen,
+        status: &str,
+        help: &str,
+    ) -> io::Result<()> {
+        self.inner.message(screen, status, help)
+    }
+
+    fn redraw(&mut self, screen: &Screen) -> io::Result<()> {
+        self.inner.redraw(screen)
+    }
+
+    fn frame(&mut self, screen: &Screen) -> io::Result<()> {
+        self.analytics.frame(screen);
+        self.inner.frame(screen)
+    }
+
+    fn finish(&mut self, screen: &Screen) -> io::Result<()> {
+        self.inner.finish(screen)?;
+        match &self.dir {
+            Some(dir) => self.analytics.save(dir, self.cell_size),
+            None => Ok(()),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
@@ -0,0 +1,106 @@
+use crate::intcode::*;
+use crate::screen::{Screen, Update};
+
+// The arcade cabinet: the game program, its screen, and its joystick. Runs
+// with no terminal attached; a renderer can follow along by looking at each
+// update to the screen as it happens.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Joystick {
+    Left,
+    Neutral,
+    Right,
+}
+
+/// Reason for the game to stop running.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    /// The game is about to read the joystick, to draw the next frame.
+    WantJoystick,
+    /// The game is over.
+    Halt,
+}
+
+#[derive(Clone)]
+pub struct Arcade {
+    process: Process,
+    screen: Screen,
+    frames: usize,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Joystick {
+    pub fn value(self) -> Intcode {
+        match self {
+            Joystick::Left => -1,
+            Joystick::Neutral => 0,
+            Joystick::Right => 1,
+        }
+    }
+}
+
+impl Arcade {
+    pub fn new(program: &[Intcode]) -> Arcade {
+        Arcade {
+            process: Process::new(program),
+            screen: Screen::new(),
+            frames: 0,
+        }
+    }
+
+    /// Load the game with the "Insert Coin" screen hacked, to play for free.
+    pub fn free_play(program: &[Intcode]) -> Arcade {
+        let mut program = program.to_vec();
+        program[0] = 2;
+        Arcade::new(&program)
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    /// Number of times the game has read the joystick.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Run the game until it reads the joystick, or until it's over, calling
+    /// `on_update` after each update is applied to the screen.
+    pub fn run<F>(&mut self, mut on_update: F) -> State
+    where
+        F: FnMut(&Screen, Update),
+    {
+        loop {
+            match self.process.exec_chunk().expect("Partial output") {
+                Chunk::Output(values) => {
+                    let update =
+                        Update::decode(values).expect("Unexpected tile id");
+                    self.screen.apply(update);
+                    on_update(&self.screen, update);
+                }
+                Chunk::WantInput => return State::WantJoystick,
+                Chunk::Halt => return State::Halt,
+            }
+        }
+    }
+
+    /// Set the joystick position for the next frame.
+    pub fn joystick(&mut self, joystick: Joystick) {
+        self.process.input.push(joystick.value());
+        self.frames += 1;
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Move the paddle towards the ball.
+pub fn autopilot(screen: &Screen) -> Joystick {
+    match (screen.ball(), screen.paddle()) {
+        (Some(ball), Some(paddle)) if ball.x > paddle.x => Joystick::Right,
+        (Some(ball), Some(paddle)) if ball.x < paddle.x => Joystick::Left,
+        _ => Joystick::Neutral,
+    }
+}
+
+// ----------------------------------------------------------------------------
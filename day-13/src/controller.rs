@@ -0,0 +1,165 @@
+use std::fmt;
+
+use crate::arcade::{autopilot, Arcade, Joystick, State};
+use crate::intcode::Intcode;
+
+// Controllers decide the joystick position for each frame.
+//
+// The naive controller follows the ball around, which keeps the paddle moving
+// most of the time. The predictive one plays ahead in a fork of the arcade,
+// with the joystick in neutral, to find where the ball comes down to the
+// paddle; then it moves the paddle there, and leaves it alone. The ball only
+// bounces off walls and blocks until it gets to the paddle, so the fork sees
+// exactly what the game will, whatever the paddle does in the meantime; only
+// where the paddle ends up on the frame of the bounce matters.
+
+pub trait Controller {
+    fn joystick(&mut self, arcade: &Arcade) -> Joystick;
+
+    /// Forget any plans, after the game jumped to another state.
+    fn reset(&mut self) {}
+}
+
+/// Moves the paddle towards the ball, every frame.
+pub struct Naive;
+
+/// Moves the paddle to where the ball will come down.
+#[derive(Debug, Default)]
+pub struct Predictive {
+    /// Where the paddle needs to be, and the frame at which the ball bounces
+    /// off it.
+    target: Option<(Intcode, usize)>,
+}
+
+/// Statistics of a game played by a controller.
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub score: Intcode,
+    pub frames: usize,
+    /// Number of frames with the joystick out of neutral.
+    pub moves: usize,
+    /// Number of blocks left each time it changed, by frame.
+    pub blocks: Vec<(usize, usize)>,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Controller for Naive {
+    fn joystick(&mut self, arcade: &Arcade) -> Joystick {
+        autopilot(arcade.screen())
+    }
+}
+
+impl Controller for Predictive {
+    fn joystick(&mut self, arcade: &Arcade) -> Joystick {
+        let paddle = match arcade.screen().paddle() {
+            Some(paddle) => paddle,
+            None => return Joystick::Neutral,
+        };
+
+        // Plan again once the ball bounced off the paddle: the paddle then
+        // has until the frame the ball comes down to get under it.
+        let now = arcade.frames();
+        if self.target.filter(|(_, frame)| now <= *frame).is_none() {
+            self.target = self.predict(arcade, paddle.y);
+        }
+
+        match self.target {
+            Some((x, _)) if x > paddle.x => Joystick::Right,
+            Some((x, _)) if x < paddle.x => Joystick::Left,
+            _ => Joystick::Neutral,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.target = None;
+    }
+}
+
+impl Predictive {
+    pub fn new() -> Predictive {
+        Default::default()
+    }
+
+    /// Play ahead until the ball is right above the paddle's row, and return
+    /// where it is and at which frame, or `None` if the game ends first.
+    fn predict(
+        &self,
+        arcade: &Arcade,
+        paddle_y: Intcode,
+    ) -> Option<(Intcode, usize)> {
+        let mut fork = arcade.clone();
+
+        loop {
+            fork.joystick(Joystick::Neutral);
+            if fork.run(|_, _| ()) == State::Halt {
+                return None;
+            }
+            match fork.screen().ball() {
+                Some(ball) if ball.y == paddle_y - 1 => {
+                    return Some((ball.x, fork.frames()))
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Stats {
+    /// Account for the joystick position about to be sent to `arcade`.
+    pub fn record(&mut self, arcade: &Arcade, joystick: Joystick) {
+        self.frames = arcade.frames() + 1;
+        if joystick != Joystick::Neutral {
+            self.moves += 1;
+        }
+        self.update(arcade);
+    }
+
+    /// Account for the state of the screen, once the game is over.
+    pub fn finish(&mut self, arcade: &Arcade) {
+        self.score = arcade.screen().score();
+        self.update(arcade);
+    }
+
+    fn update(&mut self, arcade: &Arcade) {
+        let blocks = arcade.screen().blocks();
+        if self.blocks.last().map(|(_, b)| *b) != Some(blocks) {
+            self.blocks.push((arcade.frames(), blocks));
+        }
+    }
+
+    /// Number of blocks left at `frame`.
+    pub fn blocks_at(&self, frame: usize) -> usize {
+        self.blocks
+            .iter()
+            .take_while(|(f, _)| *f <= frame)
+            .last()
+            .map_or(0, |(_, blocks)| *blocks)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Score: {}", self.score)?;
+        writeln!(
+            f,
+            "Frames: {}, joystick moves: {} ({:.1}%)",
+            self.frames,
+            self.moves,
+            100.0 * self.moves as f64 / self.frames.max(1) as f64
+        )?;
+
+        // Blocks left at 10 points in time
+        let steps: Vec<String> = (0..=10)
+            .map(|i| {
+                let frame = self.frames * i / 10;
+                format!("{}: {}", frame, self.blocks_at(frame))
+            })
+            .collect();
+        writeln!(f, "Blocks left by frame: {}", steps.join(", "))
+    }
+}
+
+// ----------------------------------------------------------------------------
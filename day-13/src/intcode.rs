@@ -1,9 +1,12 @@
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
 
 pub type Intcode = i64;
 
+#[derive(Clone)]
 pub struct Process {
     pub input: Vec<Intcode>,  // Queue of input values
     pub output: Vec<Intcode>, // Queue of output values
@@ -13,13 +16,31 @@ pub struct Process {
     rb: usize,         // Relative base
 }
 
-#[derive(PartialEq)]
+/// Why `exec()` stopped running a process. Calling it again resumes exactly
+/// where it stopped: after the `OUT` instruction, at the `IN` instruction that
+/// found no input, or at the `HALT` instruction.
+#[derive(Debug, PartialEq)]
 pub enum Status {
     WantInput,
     NewOutput,
     Halt,
 }
 
+/// What `exec_chunk()` stopped with.
+#[derive(Debug, PartialEq)]
+pub enum Chunk<const N: usize> {
+    /// `N` consecutive output values.
+    Output([Intcode; N]),
+    /// The process wants input; outputs of an incomplete chunk stay queued.
+    WantInput,
+    /// The process halted, with no outputs left over.
+    Halt,
+}
+
+/// The process halted in the middle of a chunk, leaving these outputs.
+#[derive(Debug, PartialEq)]
+pub struct PartialChunk(pub Vec<Intcode>);
+
 #[derive(FromPrimitive)]
 enum Opcode {
     ADD = 1,   // Addition
@@ -132,6 +153,7 @@ impl Process {
                     self.rb = (self.rb as i64 + p0) as usize;
                 }
                 Opcode::HALT => {
+                    self.revert_ip();
                     return Status::Halt;
                 }
             }
@@ -185,3 +207,72 @@ impl Process {
         self.ip.set(self.ip.get() - 1);
     }
 }
+
+impl Process {
+    /// Run until `N` outputs are queued, and take them as a single chunk.
+    ///
+    /// Returns an error if the process halts with fewer than `N` outputs
+    /// queued, so that partial chunks are never silently dropped.
+    pub fn exec_chunk<const N: usize>(
+        &mut self,
+    ) -> Result<Chunk<N>, PartialChunk> {
+        while self.output.len() < N {
+            match self.exec() {
+                Status::NewOutput => (),
+                Status::WantInput => return Ok(Chunk::WantInput),
+                Status::Halt if self.output.is_empty() => {
+                    return Ok(Chunk::Halt)
+                }
+                Status::Halt => {
+                    return Err(PartialChunk(self.output.drain(..).collect()))
+                }
+            }
+        }
+
+        let mut chunk = [0; N];
+        chunk.copy_from_slice(&self.output[..N]);
+        self.output.drain(..N);
+        Ok(Chunk::Output(chunk))
+    }
+}
+
+impl fmt::Display for PartialChunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Program halted with partial output {:?}", self.0)
+    }
+}
+
+impl Error for PartialChunk {}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode_conformance::{assert_conforms, impl_machine, Level};
+
+    impl_machine!(Process, Status);
+
+    #[test]
+    fn conformance() {
+        assert_conforms::<Process>(Level::Day09);
+    }
+
+    #[test]
+    fn output_chunks() {
+        // Output pairs (1, 2) and (3, 4), then a lone 5
+        let program = [104, 1, 104, 2, 104, 3, 104, 4, 104, 5, 99];
+        let mut process = Process::new(&program);
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::Output([1, 2])));
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::Output([3, 4])));
+        assert_eq!(process.exec_chunk::<2>(), Err(PartialChunk(vec![5])));
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::Halt));
+
+        // Chunks can span input requests
+        let program = [104, 1, 3, 9, 4, 9, 99, 0, 0, 0];
+        let mut process = Process::new(&program);
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::WantInput));
+        process.input.push(7);
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::Output([1, 7])));
+    }
+}
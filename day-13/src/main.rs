@@ -1,164 +1,489 @@
-use std::collections::HashMap;
-use std::io;
+use std::env;
+use std::io::{self, Read};
+use std::time::Duration;
 
-use console::{style, Term};
+use ::intcode::loader;
 
 // ----------------------------------------------------------------------------
 
 // Puzzle data types
 
 mod intcode;
-use intcode::*;
-
-const EMPTY: Intcode = 0;
-const WALL: Intcode = 1;
-const BLOCK: Intcode = 2;
-const PADDLE: Intcode = 3;
-const BALL: Intcode = 4;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point2D {
-    x: Intcode,
-    y: Intcode,
+use crate::intcode::*;
+
+mod screen;
+
+mod arcade;
+use arcade::{Arcade, State};
+
+mod render;
+use render::{default_palette, Headless, Recording, Renderer, Terminal, Theme};
+
+mod analytics;
+use analytics::Analysis;
+
+mod controller;
+use controller::{Controller, Naive, Predictive, Stats};
+
+mod play;
+use play::Pace;
+
+mod saves;
+
+use recorder::{Palette, Recorder};
+
+/// Command-line options: `[--play] [--tick MS] [--autopilot]`
+/// `[--controller naive|predictive] [--stats]`
+/// `[--output headless|terminal] [--delay MS]`
+/// `[--theme ascii|color] [--no-hud]`
+/// `[--record FILE] [--cell PIXELS] [--palette COLORS] [--analytics DIR]`
+#[derive(Default)]
+struct Options {
+    play: bool,
+    tick: Option<u64>,
+    autopilot: bool,
+    predictive: bool,
+    stats: bool,
+    output: Option<Output>,
+    delay: Option<u64>,
+    theme: Option<Theme>,
+    no_hud: bool,
+    record: Option<String>,
+    cell: Option<usize>,
+    palette: Option<Palette>,
+    analytics: Option<String>,
+}
+
+/// Where the game is drawn, besides any recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Output {
+    /// Nowhere, good for quick puzzle answers.
+    Headless,
+    /// On the terminal, good for visualization.
+    Terminal,
 }
 
 // Config
 
-//const SPEED: u64 = 0; // FAST, good for quick puzzle answer
-const SPEED: u64 = 40; // SLOW, good for visualization
+const TERMINAL_DELAY: u64 = 40; // Between frames on the terminal, in ms
+const RECORD_DELAY: u32 = 20; // Between frames of a recording, in ms
+const ANALYTICS_CELL: usize = 8; // Size of tiles in the summary image
 
 // ----------------------------------------------------------------------------
 
-fn main() {
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).expect("read_line");
+fn main() -> io::Result<()> {
+    let options = Options::parse(env::args().skip(1))?;
+
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    if options.play {
+        let pace = match options.tick {
+            Some(ms) => Pace::Tick(Duration::from_millis(ms)),
+            None => Pace::PerKey,
+        };
+        let program = parse_program(&input);
+        let mut terminal = options.terminal()?;
+        let outcome = match options.recorder() {
+            Some((recorder, path)) => play::play(
+                &program,
+                &mut Recording::new(terminal, recorder, path),
+                pace,
+                options.controller().as_mut(),
+                options.autopilot,
+            )?,
+            None => play::play(
+                &program,
+                &mut terminal,
+                pace,
+                options.controller().as_mut(),
+                options.autopilot,
+            )?,
+        };
+        match (outcome.quit, outcome.blocks) {
+            (true, _) => println!("Quit, score: {}", outcome.score),
+            (false, 0) => println!("You win! Score: {}", outcome.score),
+            (false, blocks) => println!(
+                "Game over, score: {} ({} blocks left)",
+                outcome.score, blocks
+            ),
+        }
+        return Ok(());
+    }
 
     // Part 1
 
-    let answer1 = part1(&parse_line(&line));
+    let answer1 = part1(&parse_program(&input));
     println!("Part 1: {}", answer1);
     assert_eq!(answer1, 326);
 
     // Part 2
 
-    let answer2 = part2(&parse_line(&line)).expect("part2");
-    println!("Part 2: {}", answer2);
-    assert_eq!(answer2, 15988);
+    let stats = part2(&parse_program(&input), &options).expect("part2");
+    println!("Part 2: {}", stats.score);
+    assert_eq!(stats.score, 15988);
+    if options.stats {
+        print!("{}", stats);
+    }
+
+    Ok(())
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> io::Result<Options> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--play" => options.play = true,
+                "--autopilot" => options.autopilot = true,
+                "--controller" => match args.next().as_deref() {
+                    Some("naive") => options.predictive = false,
+                    Some("predictive") => options.predictive = true,
+                    _ => return Err(invalid_arg("bad --controller".into())),
+                },
+                "--stats" => options.stats = true,
+                "--output" => match args.next().as_deref() {
+                    Some("headless") => options.output = Some(Output::Headless),
+                    Some("terminal") => options.output = Some(Output::Terminal),
+                    _ => return Err(invalid_arg("bad --output".into())),
+                },
+                "--delay" => {
+                    let ms = args
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| invalid_arg("bad --delay".into()))?;
+                    options.delay = Some(ms);
+                }
+                "--theme" => match args.next().as_deref() {
+                    Some("ascii") => options.theme = Some(Theme::Ascii),
+                    Some("color") => options.theme = Some(Theme::Color),
+                    _ => return Err(invalid_arg("bad --theme".into())),
+                },
+                "--no-hud" => options.no_hud = true,
+                "--tick" => {
+                    let ms = args
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .ok_or_else(|| invalid_arg("bad --tick".into()))?;
+                    options.tick = Some(ms);
+                }
+                "--record" => {
+                    let path = args.next().ok_or_else(|| {
+                        invalid_arg("missing --record path".into())
+                    })?;
+                    options.record = Some(path);
+                }
+                "--analytics" => {
+                    let path = args.next().ok_or_else(|| {
+                        invalid_arg("missing --analytics path".into())
+                    })?;
+                    options.analytics = Some(path);
+                }
+                "--cell" => {
+                    let cell = args
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .filter(|cell| *cell > 0)
+                        .ok_or_else(|| invalid_arg("bad --cell".into()))?;
+                    options.cell = Some(cell);
+                }
+                "--palette" => {
+                    let palette = args
+                        .next()
+                        .and_then(|s| Palette::parse(&s))
+                        .filter(|palette| palette.len() >= 5)
+                        .ok_or_else(|| invalid_arg("bad --palette".into()))?;
+                    options.palette = Some(palette);
+                }
+                _ => {
+                    return Err(invalid_arg(format!("unknown option {}", arg)))
+                }
+            }
+        }
+
+        let images = options.record.is_some() || options.analytics.is_some();
+        if options.cell.is_some() && !images {
+            return Err(invalid_arg(
+                "--cell needs --record or --analytics".into(),
+            ));
+        }
+        if options.palette.is_some() && options.record.is_none() {
+            return Err(invalid_arg("--palette needs --record".into()));
+        }
+        if options.play && options.output == Some(Output::Headless) {
+            return Err(invalid_arg("--play needs the terminal".into()));
+        }
+
+        Ok(options)
+    }
+
+    /// Where to draw the game: on the terminal, unless it's recorded.
+    fn output(&self) -> Output {
+        match (self.output, &self.record) {
+            (Some(output), _) => output,
+            (None, Some(_)) => Output::Headless,
+            (None, None) => Output::Terminal,
+        }
+    }
+
+    /// Delay between frames, in ms: none unless they're drawn on the terminal.
+    fn delay(&self) -> u64 {
+        match self.output() {
+            Output::Headless => self.delay.unwrap_or(0),
+            Output::Terminal => self.delay.unwrap_or(TERMINAL_DELAY),
+        }
+    }
+
+    fn terminal(&self) -> io::Result<Terminal> {
+        Terminal::new(self.theme.unwrap_or(Theme::Color), !self.no_hud)
+    }
+
+    /// Controller for the autopilot.
+    fn controller(&self) -> Box<dyn Controller> {
+        if self.predictive {
+            Box::new(Predictive::new())
+        } else {
+            Box::new(Naive)
+        }
+    }
+
+    /// Recorder and path for the `--record` option, if given.
+    fn recorder(&self) -> Option<(Recorder, &str)> {
+        let path = self.record.as_ref()?;
+
+        let palette = self.palette.clone().unwrap_or_else(default_palette);
+        let mut recorder = Recorder::new(palette).delay_ms(RECORD_DELAY);
+        if let Some(cell) = self.cell {
+            recorder = recorder.cell_size(cell);
+        }
+        Some((recorder, path))
+    }
+}
+
+fn invalid_arg(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
 }
 
 // ----------------------------------------------------------------------------
 
-fn parse_line(line: &str) -> Vec<Intcode> {
-    line.trim()
-        .split(',')
-        .map(|s| s.parse().expect("parse"))
-        .collect()
+fn parse_program(text: &str) -> Vec<Intcode> {
+    loader::parse(text.as_bytes()).unwrap_or_else(|e| panic!("parse: {}", e))
 }
 
 // ----------------------------------------------------------------------------
 
 fn part1(program: &[Intcode]) -> usize {
-    let mut process = Process::new(&program);
-    let mut tiles = HashMap::new();
+    let mut arcade = Arcade::new(program);
+
+    // Without coins, the game draws its screen once and halts
+    assert_eq!(arcade.run(|_, _| ()), State::Halt);
+
+    arcade.screen().blocks()
+}
+
+// ----------------------------------------------------------------------------
+
+fn part2(program: &[Intcode], options: &Options) -> io::Result<Stats> {
+    let mut controller = options.controller();
+    let mut renderer: Box<dyn Renderer> = match options.output() {
+        Output::Headless => Box::new(Headless),
+        Output::Terminal => Box::new(options.terminal()?),
+    };
+    if let Some((recorder, path)) = options.recorder() {
+        renderer = Box::new(Recording::new(renderer, recorder, path));
+    }
+    if let Some(dir) = &options.analytics {
+        let cell = options.cell.unwrap_or(ANALYTICS_CELL);
+        renderer = Box::new(Analysis::new(renderer).save_to(dir, cell));
+    }
+
+    autoplay(program, controller.as_mut(), &mut renderer, options.delay())
+}
+
+/// Play the game with a controller until it's over.
+fn autoplay<C: Controller + ?Sized, R: Renderer>(
+    program: &[Intcode],
+    controller: &mut C,
+    renderer: &mut R,
+    delay: u64,
+) -> io::Result<Stats> {
+    let mut arcade = Arcade::free_play(program);
+    let mut stats = Stats::default();
 
     loop {
-        // Run the Intcode
-        if process.exec() == Status::Halt {
+        let mut result = Ok(());
+        let state = arcade.run(|screen, update| {
+            if result.is_ok() {
+                result = renderer.update(screen, update);
+            }
+        });
+        result?;
+
+        if state == State::Halt {
             break;
         }
+        renderer.frame(arcade.screen())?;
 
-        if process.output.len() >= 3 {
-            // Interpret output values
-            let pos = (process.output[0], process.output[1]);
-            let id = process.output[2];
-            process.output.clear();
-
-            // Paint current position
-            tiles.insert(pos, id);
-        }
+        let joystick = controller.joystick(&arcade);
+        stats.record(&arcade, joystick);
+        arcade.joystick(joystick);
+        std::thread::sleep(std::time::Duration::from_millis(delay));
     }
 
-    tiles.values().filter(|id| id == &&BLOCK).count()
+    renderer.finish(arcade.screen())?;
+    stats.finish(&arcade);
+
+    Ok(stats)
 }
 
 // ----------------------------------------------------------------------------
 
-fn part2(program: &[Intcode]) -> io::Result<Intcode> {
-    let mut program = program.to_vec();
-    // Hack the "Insert Coin" screen!
-    program[0] = 2;
-    let mut process = Process::new(&program);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arcade::autopilot;
+    use saves::SaveStates;
+    use screen::{Point2D, Screen, Tile, Update};
 
-    let mut score = 0;
-    let mut ball_pos = Point2D { x: 0, y: 0 };
-    let mut paddle_pos = Point2D { x: 0, y: 0 };
+    fn program() -> Vec<Intcode> {
+        parse_program(include_str!("../input.txt"))
+    }
 
-    let term = Term::stdout();
-    term.hide_cursor()?;
-    term.clear_screen()?;
+    #[test]
+    fn screen_updates() {
+        let mut screen = Screen::new();
+        for values in &[[1, 2, 3], [6, 5, 4], [-1, 0, 12345]] {
+            screen.apply(Update::decode(*values).expect("decode"));
+        }
 
-    loop {
-        // Run the Intcode
-        let status = process.exec();
+        assert_eq!((screen.width(), screen.height()), (7, 6));
+        assert_eq!(screen.paddle(), Some(Point2D { x: 1, y: 2 }));
+        assert_eq!(screen.ball(), Some(Point2D { x: 6, y: 5 }));
+        assert_eq!(screen.score(), 12345);
+        assert_eq!(screen.to_string(), "\n\n =\n\n\n      *\n");
 
-        if status == Status::Halt {
-            break;
-        } else if status == Status::WantInput {
-            if ball_pos.x > paddle_pos.x {
-                process.input.push(1)
-            } else if ball_pos.x < paddle_pos.x {
-                process.input.push(-1)
-            } else {
-                process.input.push(0)
-            }
-            std::thread::sleep(std::time::Duration::from_millis(SPEED));
-        } else if process.output.len() >= 3 {
-            // Interpret output values
-            let pos = Point2D {
-                x: process.output[0],
-                y: process.output[1],
-            };
-
-            if pos.x == -1 && pos.y == 0 {
-                // Score mode
-                score = process.output[2];
-
-                term.move_cursor_to(0, 0)?;
-                term.write_str(&format!("score: {}", score))?;
-            } else {
-                // Tile coordinate mode
-                let id = process.output[2];
-                let c = match id {
-                    // EMPTY => ' ',
-                    // WALL => '|',
-                    // BLOCK => '#',
-                    // PADDLE => '=',
-                    // BALL => '*',
-                    EMPTY => style(' '),
-                    WALL => style(' ').on_white(),
-                    BLOCK => style(' ').on_yellow(),
-                    PADDLE => style('=').green(),
-                    BALL => style('@').red(),
-                    _ => panic!("Unexpected tile id"),
-                };
-
-                if id == BALL {
-                    ball_pos = pos;
-                } else if id == PADDLE {
-                    paddle_pos = pos;
-                }
+        // The ball moves away
+        screen.apply(Update::Tile(Point2D { x: 6, y: 5 }, Tile::Empty));
+        assert_eq!(screen.ball(), None);
+        assert_eq!(Update::decode([0, 0, 5]), None);
+    }
 
-                term.move_cursor_to(pos.x as usize, pos.y as usize)?;
-                term.write_str(&format!("{}", c))?;
+    #[test]
+    fn save_states() {
+        let mut arcade = Arcade::free_play(&program());
+        let mut saves = SaveStates::new(10);
+        let mut screens = Vec::new();
+
+        for _ in 0..30 {
+            assert_eq!(arcade.run(|_, _| ()), State::WantJoystick);
+            saves.record(&arcade);
+            screens.push(arcade.screen().clone());
+            if arcade.frames() == 5 {
+                saves.save(3, &arcade);
             }
+            arcade.joystick(autopilot(arcade.screen()));
+        }
+
+        let rewound = saves.rewind(4).expect("rewind");
+        assert_eq!(rewound.frames(), 25);
+        assert_eq!(rewound.screen(), &screens[25]);
+        // Only the last 10 frames are kept
+        assert_eq!(saves.rewind(100).expect("rewind").frames(), 19);
+
+        assert!(saves.load(4).is_none());
+        let mut loaded = saves.load(3).expect("load");
+        assert_eq!(loaded.screen(), &screens[5]);
+
+        // The game goes on from there just the same
+        loaded.joystick(autopilot(loaded.screen()));
+        loaded.run(|_, _| ());
+        assert_eq!(loaded.screen(), &screens[6]);
+        assert_eq!(saves.rewind(1).expect("rewind").frames(), 5);
+    }
 
-            process.output.clear();
+    #[test]
+    fn analytics() {
+        let mut analysis = Analysis::new(Headless);
+        let stats = autoplay(&program(), &mut Naive, &mut analysis, 0).unwrap();
+        let analytics = analysis.analytics;
+
+        // Every block scores, and that's all that scores
+        assert_eq!(analytics.hits.len(), 326);
+        let points: Intcode =
+            analytics.hits.iter().filter_map(|h| h.points).sum();
+        assert_eq!(points, 15988);
+        assert_eq!(analytics.scores.last(), Some(&(stats.frames, 15988)));
+
+        assert_eq!(analytics.frames, stats.frames);
+        assert_eq!(analytics.paddle.values().sum::<usize>(), stats.frames);
+
+        // The ball only moves diagonally between turns
+        let polyline = analytics.polyline();
+        assert_eq!(polyline.first(), analytics.ball.first());
+        for pair in polyline.windows(2) {
+            let ((f0, a), (f1, b)) = (pair[0], pair[1]);
+            let frames = (f1 - f0) as Intcode;
+            assert_eq!(
+                ((b.x - a.x).abs(), (b.y - a.y).abs()),
+                (frames, frames)
+            );
         }
     }
 
-    term.clear_screen()?;
+    #[test]
+    fn options() {
+        let parse = |args: &[&str]| {
+            Options::parse(args.iter().map(|arg| arg.to_string()))
+        };
+
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.output(), Output::Terminal);
+        assert_eq!(options.delay(), TERMINAL_DELAY);
+
+        let options = parse(&["--record", "game.gif"]).unwrap();
+        assert_eq!((options.output(), options.delay()), (Output::Headless, 0));
+
+        let options = parse(&[
+            "--record", "game.gif", "--output", "terminal", "--delay", "5",
+        ])
+        .unwrap();
+        assert_eq!((options.output(), options.delay()), (Output::Terminal, 5));
+
+        assert!(parse(&["--play", "--output", "headless"]).is_err());
+        assert!(parse(&["--theme", "sepia"]).is_err());
+        assert!(parse(&["--record"]).is_err());
+        assert!(parse(&["--analytics"]).is_err());
+    }
 
-    Ok(score)
+    #[test]
+    fn part1_headless() {
+        assert_eq!(part1(&program()), 326);
+    }
+
+    #[test]
+    fn part2_headless() {
+        let stats = autoplay(&program(), &mut Naive, &mut Headless, 0).unwrap();
+        assert_eq!(stats.score, 15988);
+        assert_eq!(stats.blocks_at(0), 326);
+        assert_eq!(stats.blocks.last(), Some(&(stats.frames, 0)));
+    }
+
+    #[test]
+    fn predictive_controller() {
+        let naive = autoplay(&program(), &mut Naive, &mut Headless, 0).unwrap();
+        let predictive =
+            autoplay(&program(), &mut Predictive::new(), &mut Headless, 0)
+                .unwrap();
+
+        assert_eq!(predictive.score, naive.score);
+        assert!(
+            predictive.moves < naive.moves / 2,
+            "{}\n{}",
+            naive,
+            predictive
+        );
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -0,0 +1,239 @@
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use console::{Key, Term};
+
+use crate::arcade::{Arcade, Joystick, State};
+use crate::controller::Controller;
+use crate::intcode::Intcode;
+use crate::render::Renderer;
+use crate::saves::{SaveStates, SLOTS};
+
+// Interactive mode, where the joystick is driven from the keyboard.
+//
+// Keys are read on their own thread, since reading a key blocks until one is
+// pressed, and the game may need to go on without one. Terminals only report
+// key presses, not releases, so each arrow key press moves the paddle for a
+// single frame.
+//
+// The game can be saved to numbered slots and loaded back, or rewound a few
+// frames at a time, to try again after missing the ball.
+
+const HELP: &str = "<-/-> move, space stay, a autopilot, p pause, q quit
+0-9 pick slot, s save, r load, b rewind";
+
+/// Frames rewound on each key press, and frames kept to rewind to.
+const REWIND: usize = 20;
+const HISTORY: usize = 200;
+
+/// How frames advance in interactive mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pace {
+    /// One frame for each key press.
+    PerKey,
+    /// One frame per tick, with the joystick in neutral if no key is pressed.
+    Tick(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Move(Joystick),
+    ToggleAutopilot,
+    Pause,
+    Slot(usize),
+    Save,
+    Load,
+    Rewind,
+    Quit,
+}
+
+/// Result of an interactive game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub score: Intcode,
+    pub blocks: usize,
+    /// Whether the game was quit before it was over.
+    pub quit: bool,
+}
+
+// ----------------------------------------------------------------------------
+
+/// Play the game from the keyboard, with `controller` as the autopilot,
+/// initially on or off.
+pub fn play<R: Renderer>(
+    program: &[Intcode],
+    renderer: &mut R,
+    pace: Pace,
+    controller: &mut dyn Controller,
+    mut autopilot_on: bool,
+) -> io::Result<Outcome> {
+    let mut arcade = Arcade::free_play(program);
+    let keys = read_keys();
+    let mut paused = false;
+    let mut quit = false;
+    let mut saves = SaveStates::new(HISTORY);
+    let mut slot = 1;
+    let mut note = String::new();
+
+    loop {
+        let mut result = Ok(());
+        let state = arcade.run(|screen, update| {
+            if result.is_ok() {
+                result = renderer.update(screen, update);
+            }
+        });
+        result?;
+
+        // Once the game is over, wait for a key, in case it's to go back
+        let mut over = state == State::Halt;
+        if !over {
+            renderer.frame(arcade.screen())?;
+            saves.record(&arcade);
+        }
+
+        let mut joystick = loop {
+            let mode = match (over, paused, autopilot_on) {
+                (true, _, _) => "GAME OVER  ",
+                (false, true, _) => "PAUSED  ",
+                (false, false, true) => "AUTOPILOT  ",
+                (false, false, false) => "",
+            };
+            let status = format!("{}slot {}  {}", mode, slot, note);
+            renderer.message(arcade.screen(), &status, HELP)?;
+            note.clear();
+
+            // Jump to a saved or earlier state, if there's one
+            let jump = match next_key(&keys, pace, paused || over).map(command)
+            {
+                Some(Some(Command::Load)) => {
+                    let loaded = saves.load(slot);
+                    if loaded.is_none() {
+                        note = "empty slot".to_string();
+                    }
+                    loaded
+                }
+                Some(Some(Command::Rewind)) => saves.rewind(REWIND),
+                Some(Some(Command::Slot(n))) => {
+                    slot = n;
+                    None
+                }
+                Some(Some(Command::Quit)) if !over => {
+                    quit = true;
+                    break Joystick::Neutral;
+                }
+                Some(Some(Command::Quit)) | Some(Some(Command::Move(_)))
+                    if over =>
+                {
+                    break Joystick::Neutral
+                }
+                Some(_) if over => None,
+                // The tick went by with no key pressed
+                None => break Joystick::Neutral,
+                Some(Some(Command::Pause)) => {
+                    paused = !paused;
+                    None
+                }
+                Some(Some(Command::ToggleAutopilot)) => {
+                    autopilot_on = !autopilot_on;
+                    None
+                }
+                Some(Some(Command::Save)) => {
+                    saves.save(slot, &arcade);
+                    note = format!("saved at frame {}", arcade.frames());
+                    None
+                }
+                Some(Some(Command::Move(joystick))) if !paused => {
+                    break joystick
+                }
+                Some(_) => None,
+            };
+
+            if let Some(state) = jump {
+                arcade = state;
+                over = false;
+                controller.reset();
+                renderer.redraw(arcade.screen())?;
+                renderer.frame(arcade.screen())?;
+                if note.is_empty() {
+                    note = format!("back to frame {}", arcade.frames());
+                }
+            }
+        };
+        if quit || over {
+            break;
+        }
+
+        if autopilot_on {
+            joystick = controller.joystick(&arcade);
+        }
+        arcade.joystick(joystick);
+    }
+
+    renderer.finish(arcade.screen())?;
+
+    Ok(Outcome {
+        score: arcade.screen().score(),
+        blocks: arcade.screen().blocks(),
+        quit,
+    })
+}
+
+/// Spawn a thread that sends each key pressed on the terminal.
+///
+/// Without a terminal, the thread ends right away, which is taken as a quit.
+fn read_keys() -> Receiver<Key> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let term = Term::stdout();
+        if !term.is_term() {
+            return;
+        }
+        while let Ok(key) = term.read_key() {
+            if sender.send(key).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+/// Wait for the next key press, or return `None` if the tick goes by first.
+fn next_key(keys: &Receiver<Key>, pace: Pace, paused: bool) -> Option<Key> {
+    match pace {
+        Pace::Tick(tick) if !paused => match keys.recv_timeout(tick) {
+            Ok(key) => Some(key),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Key::Escape),
+        },
+        _ => Some(keys.recv().unwrap_or(Key::Escape)),
+    }
+}
+
+fn command(key: Key) -> Option<Command> {
+    match key {
+        Key::ArrowLeft | Key::Char('h') => Some(Command::Move(Joystick::Left)),
+        Key::ArrowRight | Key::Char('l') => {
+            Some(Command::Move(Joystick::Right))
+        }
+        Key::ArrowDown | Key::Char(' ') | Key::Char('j') => {
+            Some(Command::Move(Joystick::Neutral))
+        }
+        Key::Char('a') => Some(Command::ToggleAutopilot),
+        Key::Char('p') => Some(Command::Pause),
+        Key::Char(c) if c.is_ascii_digit() => {
+            let slot = c.to_digit(10).expect("digit") as usize;
+            Some(Command::Slot(slot % SLOTS))
+        }
+        Key::Char('s') => Some(Command::Save),
+        Key::Char('r') => Some(Command::Load),
+        Key::Char('b') | Key::Backspace => Some(Command::Rewind),
+        Key::Char('q') | Key::Escape => Some(Command::Quit),
+        _ => None,
+    }
+}
+
+// ----------------------------------------------------------------------------
@@ -0,0 +1,238 @@
+use std::io;
+
+use console::{style, Term};
+use recorder::{Frame, Palette, Recorder};
+
+use crate::screen::{Point2D, Screen, Tile, Update};
+
+// Renderers draw the arcade screen somewhere, following each update.
+
+pub trait Renderer {
+    /// Draw a single update, already applied to `screen`.
+    fn update(&mut self, screen: &Screen, update: Update) -> io::Result<()>;
+
+    /// Show a status line and some help lines below the screen.
+    fn message(
+        &mut self,
+        _screen: &Screen,
+        _status: &str,
+        _help: &str,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Draw the whole screen again, after the game jumped to another state.
+    fn redraw(&mut self, screen: &Screen) -> io::Result<()> {
+        for y in 0..screen.height() {
+            for x in 0..screen.width() {
+                let pos = Point2D {
+                    x: x as i64,
+                    y: y as i64,
+                };
+                self.update(screen, Update::Tile(pos, screen.get(pos)))?;
+            }
+        }
+        self.update(screen, Update::Score(screen.score()))
+    }
+
+    /// Called each time the game reads the joystick, once the frame is drawn.
+    fn frame(&mut self, _screen: &Screen) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once the game is over.
+    fn finish(&mut self, _screen: &Screen) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Draws nothing, for quick puzzle answers and tests.
+pub struct Headless;
+
+/// Glyphs for the tiles drawn on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    /// Plain characters, as in the screen's text form.
+    Ascii,
+    /// Colored blocks and characters.
+    Color,
+}
+
+/// Draws on the terminal, moving the cursor to each updated tile.
+pub struct Terminal {
+    term: Term,
+    theme: Theme,
+    /// Show the score and number of blocks left below the screen.
+    hud: bool,
+}
+
+/// Records a frame each time the game reads the joystick, and saves them all
+/// once the game is over, while passing everything on to another renderer.
+pub struct Recording<R: Renderer> {
+    inner: R,
+    recorder: Recorder,
+    path: String,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Renderer for Headless {
+    fn update(&mut self, _screen: &Screen, _update: Update) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Terminal {
+    pub fn new(theme: Theme, hud: bool) -> io::Result<Terminal> {
+        let term = Term::stdout();
+        term.hide_cursor()?;
+        term.clear_screen()?;
+
+        Ok(Terminal { term, theme, hud })
+    }
+}
+
+impl Renderer for Terminal {
+    fn update(&mut self, _screen: &Screen, update: Update) -> io::Result<()> {
+        // The score is only shown by the HUD, once the frame is drawn
+        if let Update::Tile(pos, tile) = update {
+            let c = match (self.theme, tile) {
+                (Theme::Ascii, _) => style(tile.glyph()),
+                (Theme::Color, Tile::Empty) => style(' '),
+                (Theme::Color, Tile::Wall) => style(' ').on_white(),
+                (Theme::Color, Tile::Block) => style(' ').on_yellow(),
+                (Theme::Color, Tile::Paddle) => style('=').green(),
+                (Theme::Color, Tile::Ball) => style('@').red(),
+            };
+
+            self.term.move_cursor_to(pos.x as usize, pos.y as usize)?;
+            self.term.write_str(&c.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn message(
+        &mut self,
+        screen: &Screen,
+        status: &str,
+        help: &str,
+    ) -> io::Result<()> {
+        let row = screen.height() + 1;
+        self.term.move_cursor_to(0, row)?;
+        self.term.clear_line()?;
+        self.term.write_str(&style(status).bold().to_string())?;
+        for (i, line) in help.lines().enumerate() {
+            self.term.move_cursor_to(0, row + 1 + i)?;
+            self.term.clear_line()?;
+            self.term.write_str(line)?;
+        }
+        Ok(())
+    }
+
+    fn frame(&mut self, screen: &Screen) -> io::Result<()> {
+        if !self.hud {
+            return Ok(());
+        }
+
+        self.term.move_cursor_to(0, screen.height())?;
+        self.term.clear_line()?;
+        self.term.write_str(&format!(
+            "score: {}  blocks: {}",
+            screen.score(),
+            screen.blocks()
+        ))
+    }
+
+    fn finish(&mut self, _screen: &Screen) -> io::Result<()> {
+        self.term.clear_screen()?;
+        self.term.show_cursor()
+    }
+}
+
+/// Renderers picked at run time.
+impl<R: Renderer + ?Sized> Renderer for Box<R> {
+    fn update(&mut self, screen: &Screen, update: Update) -> io::Result<()> {
+        (**self).update(screen, update)
+    }
+
+    fn message(
+        &mut self,
+        screen: &Screen,
+        status: &str,
+        help: &str,
+    ) -> io::Result<()> {
+        (**self).message(screen, status, help)
+    }
+
+    fn redraw(&mut self, screen: &Screen) -> io::Result<()> {
+        (**self).redraw(screen)
+    }
+
+    fn frame(&mut self, screen: &Screen) -> io::Result<()> {
+        (**self).frame(screen)
+    }
+
+    fn finish(&mut self, screen: &Screen) -> io::Result<()> {
+        (**self).finish(screen)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Colors for each tile, by tile id.
+pub fn default_palette() -> Palette {
+    Palette::parse("000000,c0c0c0,ffd700,00c000,ff2020").expect("palette")
+}
+
+impl<R: Renderer> Recording<R> {
+    pub fn new(inner: R, recorder: Recorder, path: &str) -> Recording<R> {
+        Recording {
+            inner,
+            recorder,
+            path: path.to_string(),
+        }
+    }
+
+    fn capture(&mut self, screen: &Screen) {
+        let mut frame = Frame::new(screen.width(), screen.height());
+        for y in 0..screen.height() {
+            for x in 0..screen.width() {
+                let pos = Point2D {
+                    x: x as i64,
+                    y: y as i64,
+                };
+                frame.set(x, y, screen.get(pos) as u8);
+            }
+        }
+        self.recorder.push(frame);
+    }
+}
+
+impl<R: Renderer> Renderer for Recording<R> {
+    fn update(&mut self, screen: &Screen, update: Update) -> io::Result<()> {
+        self.inner.update(screen, update)
+    }
+
+    fn message(
+        &mut self,
+        screen: &Screen,
+        status: &str,
+        help: &str,
+    ) -> io::Result<()> {
+        self.inner.message(screen, status, help)
+    }
+
+    fn frame(&mut self, screen: &Screen) -> io::Result<()> {
+        self.capture(screen);
+        self.inner.frame(screen)
+    }
+
+    fn finish(&mut self, screen: &Screen) -> io::Result<()> {
+        self.capture(screen);
+        self.inner.finish(screen)?;
+        self.recorder.save(&self.path)?;
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+use crate::arcade::Arcade;
+
+// Save states: full copies of the arcade, VM and screen alike, kept in
+// numbered slots, along with a rolling buffer of the latest frames to rewind.
+//
+// Copies are taken while the game waits for the joystick, so a state loaded
+// back is ready for the next joystick position.
+
+/// Number of save slots, one per digit key.
+pub const SLOTS: usize = 10;
+
+pub struct SaveStates {
+    slots: Vec<Option<Arcade>>,
+    /// Latest frames, oldest first, ending with the current one.
+    history: VecDeque<Arcade>,
+    capacity: usize,
+}
+
+// ----------------------------------------------------------------------------
+
+impl SaveStates {
+    /// Empty slots, with room to rewind up to `capacity` frames.
+    pub fn new(capacity: usize) -> SaveStates {
+        SaveStates {
+            slots: vec![None; SLOTS],
+            history: VecDeque::with_capacity(capacity + 1),
+            capacity,
+        }
+    }
+
+    pub fn save(&mut self, slot: usize, arcade: &Arcade) {
+        self.slots[slot] = Some(arcade.clone());
+    }
+
+    /// State saved in `slot`, if any. Frames before it can't be rewound to
+    /// anymore.
+    pub fn load(&mut self, slot: usize) -> Option<Arcade> {
+        let arcade = self.slots[slot].clone()?;
+        self.history.clear();
+        self.history.push_back(arcade.clone());
+        Some(arcade)
+    }
+
+    /// Keep the state of a new frame, forgetting the oldest one if needed.
+    pub fn record(&mut self, arcade: &Arcade) {
+        if self.history.len() > self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(arcade.clone());
+    }
+
+    /// State `frames` frames back, or as far back as kept, if any frame was
+    /// recorded. Frames after it are forgotten.
+    pub fn rewind(&mut self, frames: usize) -> Option<Arcade> {
+        let keep = self.history.len().saturating_sub(frames).max(1);
+        self.history.truncate(keep);
+        self.history.back().cloned()
+    }
+}
+
+// ----------------------------------------------------------------------------
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::intcode::Intcode;
+
+// Model of the arcade cabinet screen, as drawn by the game program. It only
+// keeps track of what's on it; drawing it somewhere is up to a renderer.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point2D {
+    pub x: Intcode,
+    pub y: Intcode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+/// A change to the screen, from one triplet of output values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Update {
+    Tile(Point2D, Tile),
+    Score(Intcode),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Screen {
+    tiles: HashMap<Point2D, Tile>,
+    width: usize,
+    height: usize,
+    score: Intcode,
+    ball: Option<Point2D>,
+    paddle: Option<Point2D>,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Tile {
+    pub fn from_id(id: Intcode) -> Option<Tile> {
+        match id {
+            0 => Some(Tile::Empty),
+            1 => Some(Tile::Wall),
+            2 => Some(Tile::Block),
+            3 => Some(Tile::Paddle),
+            4 => Some(Tile::Ball),
+            _ => None,
+        }
+    }
+
+    /// Plain ASCII character for the tile.
+    pub fn glyph(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '|',
+            Tile::Block => '#',
+            Tile::Paddle => '=',
+            Tile::Ball => '*',
+        }
+    }
+}
+
+impl Update {
+    /// Interpret an `[x, y, value]` triplet of output values.
+    ///
+    /// Returns `None` if the value is not a valid tile id.
+    pub fn decode([x, y, value]: [Intcode; 3]) -> Option<Update> {
+        if x == -1 && y == 0 {
+            Some(Update::Score(value))
+        } else {
+            Tile::from_id(value)
+                .map(|tile| Update::Tile(Point2D { x, y }, tile))
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Screen {
+    pub fn new() -> Screen {
+        Default::default()
+    }
+
+    pub fn apply(&mut self, update: Update) {
+        match update {
+            Update::Score(score) => self.score = score,
+            Update::Tile(pos, tile) => {
+                assert!(pos.x >= 0 && pos.y >= 0, "Tile off the screen");
+                self.width = self.width.max(pos.x as usize + 1);
+                self.height = self.height.max(pos.y as usize + 1);
+
+                // Forget the ball or paddle if they get drawn over
+                if self.ball == Some(pos) {
+                    self.ball = None;
+                }
+                if self.paddle == Some(pos) {
+                    self.paddle = None;
+                }
+                match tile {
+                    Tile::Ball => self.ball = Some(pos),
+                    Tile::Paddle => self.paddle = Some(pos),
+                    _ => (),
+                }
+
+                self.tiles.insert(pos, tile);
+            }
+        }
+    }
+
+    /// Tile at `pos`, with tiles never drawn being empty.
+    pub fn get(&self, pos: Point2D) -> Tile {
+        self.tiles.get(&pos).copied().unwrap_or(Tile::Empty)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn score(&self) -> Intcode {
+        self.score
+    }
+
+    pub fn ball(&self) -> Option<Point2D> {
+        self.ball
+    }
+
+    pub fn paddle(&self) -> Option<Point2D> {
+        self.paddle
+    }
+
+    pub fn count(&self, tile: Tile) -> usize {
+        self.tiles.values().filter(|t| **t == tile).count()
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.count(Tile::Block)
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height() as Intcode {
+            let row: String = (0..self.width() as Intcode)
+                .map(|x| self.get(Point2D { x, y }).glyph())
+                .collect();
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
@@ -0,0 +1,192 @@
+use crate::{Case, Level};
+
+// Most programs come from the puzzle descriptions of days 2, 5 and 9; the
+// rest exercise one feature each, and output a value to check the result.
+
+pub fn cases() -> Vec<Case> {
+    vec![
+        // Day 5 features
+        // --------------
+        Case {
+            name: "add_position",
+            level: Level::Day05,
+            program: &[1, 7, 8, 9, 4, 9, 99, 30, 12, 0],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "add_immediate",
+            level: Level::Day05,
+            program: &[1101, 30, 12, 7, 4, 7, 99, 0],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "add_negative",
+            level: Level::Day05,
+            program: &[1101, 100, -58, 7, 4, 7, 99, 0],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "mul_position",
+            level: Level::Day05,
+            program: &[2, 7, 8, 9, 4, 9, 99, 6, 7, 0],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "mul_mixed",
+            level: Level::Day05,
+            program: &[1002, 7, 3, 7, 4, 7, 99, 14],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "echo",
+            level: Level::Day05,
+            program: &[3, 0, 4, 0, 99],
+            runs: &[(&[7], &[7]), (&[-1], &[-1])],
+        },
+        Case {
+            name: "output_immediate",
+            level: Level::Day05,
+            program: &[104, 42, 99],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "eq_position",
+            level: Level::Day05,
+            program: &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            runs: &[(&[8], &[1]), (&[5], &[0])],
+        },
+        Case {
+            name: "lt_position",
+            level: Level::Day05,
+            program: &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            runs: &[(&[5], &[1]), (&[8], &[0])],
+        },
+        Case {
+            name: "eq_immediate",
+            level: Level::Day05,
+            program: &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            runs: &[(&[8], &[1]), (&[9], &[0])],
+        },
+        Case {
+            name: "lt_immediate",
+            level: Level::Day05,
+            program: &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            runs: &[(&[5], &[1]), (&[9], &[0])],
+        },
+        Case {
+            name: "jif_position",
+            level: Level::Day05,
+            program: &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+            runs: &[(&[0], &[0]), (&[5], &[1])],
+        },
+        Case {
+            name: "jit_immediate",
+            level: Level::Day05,
+            program: &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1],
+            runs: &[(&[0], &[0]), (&[5], &[1])],
+        },
+        Case {
+            name: "compare_to_8",
+            level: Level::Day05,
+            program: &[
+                3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20,
+                31, 1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1,
+                46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1,
+                46, 98, 99,
+            ],
+            runs: &[(&[7], &[999]), (&[8], &[1000]), (&[9], &[1001])],
+        },
+        Case {
+            name: "self_modify_halt",
+            level: Level::Day05,
+            program: &[1002, 4, 3, 4, 33],
+            runs: &[(&[], &[])],
+        },
+        Case {
+            name: "self_modify_opcode",
+            level: Level::Day05,
+            program: &[1101, 100, 4, 4, 4, 42, 99],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "suspend_between_inputs",
+            level: Level::Day05,
+            program: &[3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0],
+            runs: &[(&[40, 2], &[42])],
+        },
+        Case {
+            name: "output_before_input",
+            level: Level::Day05,
+            program: &[104, 1, 3, 8, 4, 8, 99, 0, 0],
+            runs: &[(&[5], &[1, 5])],
+        },
+        // Day 9 features
+        // --------------
+        Case {
+            name: "quine",
+            level: Level::Day09,
+            program: &[
+                109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006,
+                101, 0, 99,
+            ],
+            runs: &[(
+                &[],
+                &[
+                    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101,
+                    1006, 101, 0, 99,
+                ],
+            )],
+        },
+        Case {
+            name: "large_mul",
+            level: Level::Day09,
+            program: &[1102, 34915192, 34915192, 7, 4, 7, 99, 0],
+            runs: &[(&[], &[1219070632396864])],
+        },
+        Case {
+            name: "large_negative_mul",
+            level: Level::Day09,
+            program: &[1102, -3_000_000_000, 3_000_000_000, 7, 4, 7, 99, 0],
+            runs: &[(&[], &[-9_000_000_000_000_000_000])],
+        },
+        Case {
+            name: "large_output",
+            level: Level::Day09,
+            program: &[104, 1125899906842624, 99],
+            runs: &[(&[], &[1125899906842624])],
+        },
+        Case {
+            name: "memory_beyond_program",
+            level: Level::Day09,
+            program: &[1101, 20, 22, 1000, 4, 1000, 99],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "relative_input",
+            level: Level::Day09,
+            program: &[109, 10, 203, 5, 204, 5, 99],
+            runs: &[(&[77], &[77])],
+        },
+        Case {
+            name: "relative_write",
+            level: Level::Day09,
+            program: &[109, 20, 21101, 40, 2, 0, 204, 0, 99],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "relative_base_negative",
+            level: Level::Day09,
+            program: &[109, 30, 109, -10, 204, -13, 99, 42],
+            runs: &[(&[], &[42])],
+        },
+        Case {
+            name: "relative_compare_jump",
+            level: Level::Day09,
+            program: &[
+                109, 100, 21107, 3, 4, 0, 1205, 0, 13, 104, 0, 99, 99, 104, 1,
+                99,
+            ],
+            runs: &[(&[], &[1])],
+        },
+    ]
+}
@@ -0,0 +1,234 @@
+// Conformance suite for Intcode virtual machines.
+//
+// Each day that runs Intcode programs has its own copy of the VM, so this
+// crate collects the expected behaviour in a single place: every opcode, every
+// parameter mode, large numbers, self-modifying programs, and suspending for
+// input. Any VM can be checked against it by implementing `Machine`.
+//
+// Each case runs twice. First, inputs are never given ahead of time: each one
+// is only provided after the machine stops to ask for it, and only after
+// checking that asking again, without providing it, leaves the machine waiting
+// in the same place. A halted machine must also stay halted. Then, all inputs
+// are queued before starting, and the outputs must be the same: resuming after
+// any suspension must not lose or repeat anything.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+mod cases;
+pub use cases::cases;
+
+pub type Intcode = i64;
+
+/// Reason for a `Machine` to stop running.
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    /// The program output a value.
+    Output(Intcode),
+    /// The program wants an input value, and none is queued.
+    NeedInput,
+    /// The program ran its `HALT` instruction.
+    Halt,
+}
+
+/// Interface for a VM to be checked by the suite.
+pub trait Machine {
+    /// Load a program into a new machine.
+    fn load(program: &[Intcode]) -> Self;
+
+    /// Queue an input value.
+    fn input(&mut self, value: Intcode);
+
+    /// Run until the next output, input request, or halt.
+    fn resume(&mut self) -> Event;
+}
+
+/// Implement `Machine` for a VM with the interface of the Intcode days: a
+/// `new(program)` constructor, `input` and `output` queues, and an `exec()`
+/// that returns a `Status` of `NewOutput`, `WantInput` or `Halt`.
+#[macro_export]
+macro_rules! impl_machine {
+    ($process:ident, $status:ident) => {
+        impl $crate::Machine for $process {
+            fn load(program: &[$crate::Intcode]) -> $process {
+                $process::new(program)
+            }
+
+            fn input(&mut self, value: $crate::Intcode) {
+                self.input.push(value);
+            }
+
+            fn resume(&mut self) -> $crate::Event {
+                match self.exec() {
+                    $status::NewOutput => {
+                        $crate::Event::Output(self.output.remove(0))
+                    }
+                    $status::WantInput => $crate::Event::NeedInput,
+                    $status::Halt => $crate::Event::Halt,
+                }
+            }
+        }
+    };
+}
+
+/// Features required by a case, named after the day that introduced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Opcodes 1 to 8, position and immediate modes.
+    Day05,
+    /// Relative mode, relative base offset, large numbers and extra memory.
+    Day09,
+}
+
+pub struct Case {
+    pub name: &'static str,
+    pub level: Level,
+    pub program: &'static [Intcode],
+    /// Runs of the program, as pairs of inputs and expected outputs.
+    pub runs: &'static [(&'static [Intcode], &'static [Intcode])],
+}
+
+/// Case that didn't behave as expected.
+#[derive(Debug)]
+pub struct Failure {
+    pub case: &'static str,
+    pub inputs: &'static [Intcode],
+    pub reason: String,
+}
+
+// ----------------------------------------------------------------------------
+
+/// Run all cases up to `level`, returning the ones that failed.
+pub fn check<M: Machine>(level: Level) -> Vec<Failure> {
+    let mut failures = Vec::new();
+
+    for case in cases().iter().filter(|case| case.level <= level) {
+        for (inputs, expected) in case.runs {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                run::<M>(case.program, inputs, expected)?;
+                run_queued::<M>(case.program, inputs, expected)
+                    .map_err(|reason| format!("with queued inputs, {}", reason))
+            }));
+            let reason = match result {
+                Ok(Ok(())) => continue,
+                Ok(Err(reason)) => reason,
+                Err(err) => format!("panicked: {}", panic_message(err)),
+            };
+            failures.push(Failure {
+                case: case.name,
+                inputs,
+                reason,
+            });
+        }
+    }
+
+    failures
+}
+
+/// Run all cases up to `level`, and panic with a list of failures if any.
+pub fn assert_conforms<M: Machine>(level: Level) {
+    let failures = check::<M>(level);
+
+    if !failures.is_empty() {
+        let list: Vec<String> =
+            failures.iter().map(|failure| failure.to_string()).collect();
+        panic!(
+            "{} conformance failures:\n{}",
+            failures.len(),
+            list.join("\n")
+        );
+    }
+}
+
+fn run<M: Machine>(
+    program: &[Intcode],
+    inputs: &[Intcode],
+    expected: &[Intcode],
+) -> Result<(), String> {
+    let mut machine = M::load(program);
+    let mut inputs = inputs.iter();
+    let mut outputs = Vec::new();
+
+    loop {
+        match machine.resume() {
+            Event::Output(value) => {
+                outputs.push(value);
+                if outputs.len() > expected.len() {
+                    return Err(format!("too many outputs: {:?}", outputs));
+                }
+            }
+            Event::NeedInput => {
+                let value = inputs.next().ok_or("asked for too many inputs")?;
+                if machine.resume() != Event::NeedInput {
+                    return Err(String::from(
+                        "didn't ask again for the input it was waiting for",
+                    ));
+                }
+                machine.input(*value);
+            }
+            Event::Halt => break,
+        }
+    }
+
+    if machine.resume() != Event::Halt {
+        return Err(String::from("didn't stay halted"));
+    }
+    if outputs != expected {
+        return Err(format!("expected {:?}, got {:?}", expected, outputs));
+    }
+    if inputs.next().is_some() {
+        return Err(String::from("halted before reading all inputs"));
+    }
+
+    Ok(())
+}
+
+fn run_queued<M: Machine>(
+    program: &[Intcode],
+    inputs: &[Intcode],
+    expected: &[Intcode],
+) -> Result<(), String> {
+    let mut machine = M::load(program);
+    for value in inputs {
+        machine.input(*value);
+    }
+
+    let mut outputs = Vec::new();
+
+    loop {
+        match machine.resume() {
+            Event::Output(value) => outputs.push(value),
+            Event::NeedInput => {
+                return Err(String::from("asked for more input"))
+            }
+            Event::Halt => break,
+        }
+        if outputs.len() > expected.len() {
+            return Err(format!("too many outputs: {:?}", outputs));
+        }
+    }
+
+    if outputs != expected {
+        return Err(format!("expected {:?}, got {:?}", expected, outputs));
+    }
+
+    Ok(())
+}
+
+fn panic_message(err: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(msg) = err.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = err.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        String::from("Unknown error")
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:?}: {}", self.case, self.inputs, self.reason)
+    }
+}
+
+// ----------------------------------------------------------------------------
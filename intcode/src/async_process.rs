@@ -0,0 +1,505 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::intcode::{Intcode, Process, Status};
+
+// Async interface over `Process`, so that Intcode machines can be composed
+// with other async tasks. Nothing here depends on a particular executor: the
+// futures only use the `Waker` they are polled with. A minimal executor is
+// included anyway, for programs and tests that don't already have one.
+//
+// Note that the process itself still runs synchronously: awaiting an output
+// executes instructions until one is produced, without yielding in between.
+
+/// Asynchronous source of input values.
+///
+/// This is the same contract as the `Stream` trait of the `futures` crate:
+/// `Poll::Ready(None)` means that no more values will ever be produced.
+pub trait InputStream {
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Intcode>>;
+}
+
+/// The process wanted input, but its input stream has ended.
+#[derive(Debug, PartialEq)]
+pub struct InputClosed;
+
+/// Tasks were left waiting on each other's channels, or on nothing at all.
+#[derive(Debug, PartialEq)]
+pub struct Deadlock;
+
+pub struct AsyncProcess<S> {
+    process: Process,
+    input: S,
+}
+
+// ----------------------------------------------------------------------------
+
+impl<S: InputStream + Unpin> AsyncProcess<S> {
+    pub fn new(process: Process, input: S) -> AsyncProcess<S> {
+        AsyncProcess { process, input }
+    }
+
+    /// Run the process until it produces the next output value.
+    ///
+    /// Returns `Ok(None)` once the process halts.
+    pub async fn next_output(
+        &mut self,
+    ) -> Result<Option<Intcode>, InputClosed> {
+        if !self.process.output.is_empty() {
+            return Ok(Some(self.process.output.remove(0)));
+        }
+
+        loop {
+            match self.process.exec() {
+                Status::Halt => return Ok(None),
+                Status::NewOutput => {
+                    return Ok(Some(self.process.output.remove(0)))
+                }
+                Status::WantInput => match next(&mut self.input).await {
+                    Some(value) => self.process.input.push(value),
+                    None => return Err(InputClosed),
+                },
+            }
+        }
+    }
+
+    /// Run the process until it halts, sending all outputs to `output`.
+    pub async fn run(mut self, output: Sender) -> Result<(), InputClosed> {
+        while let Some(value) = self.next_output().await? {
+            output.send(value);
+        }
+
+        Ok(())
+    }
+
+    /// Get back the process, e.g. to inspect its memory.
+    pub fn into_inner(self) -> Process {
+        self.process
+    }
+}
+
+/// Future for the next value of an `InputStream`.
+fn next<S: InputStream + Unpin>(
+    stream: &mut S,
+) -> impl Future<Output = Option<Intcode>> + '_ {
+    std::future::poll_fn(move |cx| Pin::new(&mut *stream).poll_next(cx))
+}
+
+// ----------------------------------------------------------------------------
+
+/// Input stream that yields the values of an iterator, and then ends.
+pub struct Iter<I>(I);
+
+pub fn iter<I: IntoIterator<Item = Intcode>>(values: I) -> Iter<I::IntoIter> {
+    Iter(values.into_iter())
+}
+
+impl<I: Iterator<Item = Intcode> + Unpin> InputStream for Iter<I> {
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context,
+    ) -> Poll<Option<Intcode>> {
+        Poll::Ready(self.0.next())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// Unbounded channel, which can connect the output of an `AsyncProcess` to the
+// input of another one. The stream ends when all senders have been dropped.
+//
+// Both ends stay on the thread that created them, and every channel of a
+// thread is registered there. So while the executor of that thread has no
+// task to poll, it can tell the tasks that only channels can wake: as nobody
+// else can send to these channels, they're waiting for each other.
+
+struct Shared {
+    queue: VecDeque<Intcode>,
+    senders: usize,
+    waker: Option<Waker>,
+}
+
+pub struct Sender(Rc<RefCell<Shared>>);
+
+pub struct Receiver(Rc<RefCell<Shared>>);
+
+thread_local! {
+    static CHANNELS: RefCell<Vec<Weak<RefCell<Shared>>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+pub fn channel() -> (Sender, Receiver) {
+    let shared = Rc::new(RefCell::new(Shared {
+        queue: VecDeque::new(),
+        senders: 1,
+        waker: None,
+    }));
+
+    CHANNELS.with(|channels| {
+        let mut channels = channels.borrow_mut();
+        channels.retain(|channel| channel.strong_count() > 0);
+        channels.push(Rc::downgrade(&shared));
+    });
+
+    (Sender(Rc::clone(&shared)), Receiver(shared))
+}
+
+/// Number of channels of this thread that hold `waker`.
+fn channel_wakers(waker: &Waker) -> usize {
+    CHANNELS.with(|channels| {
+        channels
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|shared| match &shared.borrow().waker {
+                Some(held) => held.will_wake(waker),
+                None => false,
+            })
+            .count()
+    })
+}
+
+impl Sender {
+    pub fn send(&self, value: Intcode) {
+        let mut shared = self.0.borrow_mut();
+        shared.queue.push_back(value);
+        let waker = shared.waker.take();
+        drop(shared);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Sender {
+        self.0.borrow_mut().senders += 1;
+        Sender(Rc::clone(&self.0))
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut shared = self.0.borrow_mut();
+        shared.senders -= 1;
+        let waker = match shared.senders {
+            0 => shared.waker.take(),
+            _ => None,
+        };
+        drop(shared);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Receiver {
+    /// Wait for the next value.
+    pub async fn recv(&mut self) -> Option<Intcode> {
+        next(self).await
+    }
+}
+
+impl InputStream for Receiver {
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Intcode>> {
+        let mut shared = self.0.borrow_mut();
+
+        if let Some(value) = shared.queue.pop_front() {
+            Poll::Ready(Some(value))
+        } else if shared.senders == 0 {
+            Poll::Ready(None)
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Single-threaded executor, running a set of tasks until all of them finish.
+#[derive(Default)]
+pub struct LocalExecutor {
+    tasks: Vec<Task>,
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    waker: Arc<TaskWaker>,
+}
+
+struct TaskWaker {
+    woken: AtomicBool,
+    thread: Thread,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+impl Task {
+    /// Whether nothing but the channels of this thread can wake the task.
+    /// Wakers are woken before they're dropped, so a task woken and left
+    /// without wakers is still seen as woken.
+    fn stuck(&self) -> bool {
+        let waker = Waker::from(Arc::clone(&self.waker));
+        // Not counting the executor's own reference, and `waker`
+        let held = Arc::strong_count(&self.waker) - 2;
+
+        !self.waker.woken.load(Ordering::SeqCst)
+            && held == channel_wakers(&waker)
+    }
+}
+
+impl LocalExecutor {
+    pub fn new() -> LocalExecutor {
+        Default::default()
+    }
+
+    pub fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) {
+        self.tasks.push(Task {
+            future: Box::pin(future),
+            waker: Arc::new(TaskWaker {
+                woken: AtomicBool::new(true), // Poll at least once
+                thread: thread::current(),
+            }),
+        });
+    }
+
+    /// Poll tasks as they get woken, until all of them have finished, parking
+    /// the thread while none is woken.
+    ///
+    /// Tasks can be woken by each other, by other threads, or by anything
+    /// else holding their wakers. Once the tasks still waiting can only be
+    /// woken by channels they're all waiting on, or by nothing at all, they
+    /// never will be: they're left undone, and a `Deadlock` is returned.
+    pub fn run(&mut self) -> Result<(), Deadlock> {
+        while !self.tasks.is_empty() {
+            let mut progress = false;
+
+            let mut i = 0;
+            while i < self.tasks.len() {
+                let task = &mut self.tasks[i];
+                if !task.waker.woken.swap(false, Ordering::SeqCst) {
+                    i += 1;
+                    continue;
+                }
+                progress = true;
+
+                let waker = Waker::from(Arc::clone(&task.waker));
+                let mut cx = Context::from_waker(&waker);
+                match task.future.as_mut().poll(&mut cx) {
+                    Poll::Ready(()) => {
+                        self.tasks.swap_remove(i);
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+
+            if !progress {
+                if self.tasks.iter().all(Task::stuck) {
+                    return Err(Deadlock);
+                }
+                thread::park();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Run a single future to completion on the current thread.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(TaskWaker {
+        woken: AtomicBool::new(false),
+        thread: thread::current(),
+    });
+    let waker_ref = Waker::from(Arc::clone(&waker));
+    let mut cx = Context::from_waker(&waker_ref);
+
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        while !waker.woken.swap(false, Ordering::SeqCst) {
+            thread::park();
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::sync::mpsc;
+
+    // Amplifier program from Day 7, Part Two examples
+    const AMPLIFIER: [Intcode; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27,
+        1001, 28, -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    #[test]
+    fn outputs_from_iter() {
+        // Echo each input, multiplied by 2, until the input is 0
+        let program =
+            [3, 20, 1006, 20, 14, 1002, 20, 2, 20, 4, 20, 1105, 1, 0, 99];
+        let mut process =
+            AsyncProcess::new(Process::new(&program), iter(vec![1, 5, 0]));
+
+        block_on(async {
+            assert_eq!(process.next_output().await, Ok(Some(2)));
+            assert_eq!(process.next_output().await, Ok(Some(10)));
+            assert_eq!(process.next_output().await, Ok(None));
+        });
+
+        let mut process =
+            AsyncProcess::new(Process::new(&program), iter(vec![7]));
+
+        block_on(async {
+            assert_eq!(process.next_output().await, Ok(Some(14)));
+            assert_eq!(process.next_output().await, Err(InputClosed));
+        });
+    }
+
+    #[test]
+    fn feedback_loop() {
+        let phases = [9, 8, 7, 6, 5];
+        let n = phases.len();
+        let mut executor = LocalExecutor::new();
+
+        // Channel i feeds amplifier i
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            phases.iter().map(|_| channel()).unzip();
+        for (tx, phase) in senders.iter().zip(&phases) {
+            tx.send(*phase);
+        }
+        senders[0].send(0);
+
+        let thruster = Rc::new(RefCell::new(None));
+
+        for (i, rx) in receivers.into_iter().enumerate() {
+            let mut amplifier = AsyncProcess::new(Process::new(&AMPLIFIER), rx);
+            let next_tx = senders[(i + 1) % n].clone();
+            let thruster = Rc::clone(&thruster);
+
+            executor.spawn(async move {
+                while let Some(value) = amplifier.next_output().await.unwrap() {
+                    next_tx.send(value);
+                    if i == n - 1 {
+                        *thruster.borrow_mut() = Some(value);
+                    }
+                }
+            });
+        }
+        drop(senders);
+
+        assert_eq!(executor.run(), Ok(()));
+        assert_eq!(*thruster.borrow(), Some(139629729));
+    }
+
+    #[test]
+    fn woken_by_thread() {
+        // Timer-like source, waking the task from another thread
+        let (waker_tx, waker_rx) = mpsc::channel::<Waker>();
+        let timer = thread::spawn(move || waker_rx.recv().unwrap().wake());
+
+        let done = Rc::new(Cell::new(false));
+        let flag = Rc::clone(&done);
+        let mut executor = LocalExecutor::new();
+        executor.spawn(async move {
+            let mut waiting = false;
+            std::future::poll_fn(|cx| {
+                if waiting {
+                    return Poll::Ready(());
+                }
+                waiting = true;
+                waker_tx.send(cx.waker().clone()).unwrap();
+                Poll::Pending
+            })
+            .await;
+            flag.set(true);
+        });
+
+        assert_eq!(executor.run(), Ok(()));
+        timer.join().unwrap();
+        assert!(done.get());
+
+        // Nothing can wake a task that keeps no waker
+        executor.spawn(std::future::pending());
+        assert_eq!(executor.run(), Err(Deadlock));
+        assert_eq!(executor.tasks.len(), 1);
+    }
+
+    #[test]
+    fn deadlock() {
+        // Two processes that both start by waiting for the other one
+        let program = [3, 9, 4, 9, 99];
+        let (a_tx, a_rx) = channel();
+        let (b_tx, b_rx) = channel();
+
+        let mut executor = LocalExecutor::new();
+        let a = AsyncProcess::new(Process::new(&program), a_rx);
+        executor.spawn(async move {
+            a.run(b_tx).await.unwrap();
+        });
+        let b = AsyncProcess::new(Process::new(&program), b_rx);
+        executor.spawn(async move {
+            b.run(a_tx).await.unwrap();
+        });
+
+        assert_eq!(executor.run(), Err(Deadlock));
+        assert_eq!(executor.tasks.len(), 2);
+    }
+
+    #[test]
+    fn simulated_device() {
+        // Add up pairs of inputs until the input is 0
+        let program = [
+            3, 20, 1006, 20, 16, 3, 21, 1, 20, 21, 22, 4, 22, 1105, 1, 0, 99,
+        ];
+        let (input_tx, input_rx) = channel();
+        let (output_tx, mut output_rx) = channel();
+        let process = AsyncProcess::new(Process::new(&program), input_rx);
+
+        let mut executor = LocalExecutor::new();
+        executor.spawn(async move {
+            process.run(output_tx).await.unwrap();
+        });
+
+        // Device that sends pairs and checks each sum, before the next pair
+        executor.spawn(async move {
+            for (a, b) in [(1, 2), (30, 40), (-5, 5)].iter() {
+                input_tx.send(*a);
+                input_tx.send(*b);
+                assert_eq!(output_rx.recv().await, Some(a + b));
+            }
+            input_tx.send(0);
+            assert_eq!(output_rx.recv().await, None);
+        });
+
+        assert_eq!(executor.run(), Ok(()));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+use intcode::disasm::Instruction;
+use intcode::memdiff::Snapshot;
+use intcode::stack::CallStack;
+use intcode::*;
+
+const HELP: &str = "\
+Commands:
+  s, step [N]       Execute N instructions (default: 1)
+  c, continue       Run until a breakpoint, input request, or halt
+  b, break ADDR     Toggle a breakpoint at ADDR
+  w, watch ADDR     Toggle a watchpoint, stopping when ADDR changes value
+  i, input VALUE..  Queue input values
+  r, regs           Show registers and queues
+  bt, backtrace     Show the call stack, as guessed from the relative base
+  x ADDR [N]        Examine N memory cells starting at ADDR (default: 8)
+  set ADDR VALUE    Write VALUE into memory at ADDR
+  snap              Take a snapshot of memory, to compare against later
+  diff              Show the memory cells changed since the last snapshot
+  l, list [ADDR]    Disassemble 10 instructions at ADDR (default: ip)
+  h, help           Show this help
+  q, quit           Exit the debugger";
+
+// ----------------------------------------------------------------------------
+
+/// Interactive debugger, driven by commands read from stdin.
+pub fn debug(program: &[Intcode], inputs: Vec<Intcode>) -> io::Result<()> {
+    let mut process = Process::new(program);
+    process.input = inputs;
+
+    let mut stack = CallStack::new();
+    let mut breakpoints = HashSet::new();
+    let mut watchpoints = HashSet::new();
+    let mut snapshot = Snapshot::take(&process);
+    let mut halted = false;
+
+    println!("Type 'h' for help.");
+    show_current(&process);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let arg = |i: usize| words.get(i).and_then(|w| w.parse().ok());
+
+        match words.first().copied().unwrap_or("") {
+            "s" | "step" => {
+                for _ in 0..arg(1).unwrap_or(1) {
+                    if halted || !step(&mut process, &mut stack, &mut halted) {
+                        break;
+                    }
+                }
+                show_current(&process);
+            }
+            "c" | "continue" => {
+                let mut watched = watch_values(&process, &watchpoints);
+                while !halted && step(&mut process, &mut stack, &mut halted) {
+                    if breakpoints.contains(&process.ip()) {
+                        println!("Breakpoint at {}", process.ip());
+                        break;
+                    }
+                    let values = watch_values(&process, &watchpoints);
+                    if values != watched {
+                        for (old, new) in watched.iter().zip(&values) {
+                            if old != new {
+                                println!(
+                                    "Watchpoint {}: {} -> {}",
+                                    old.0, old.1, new.1
+                                );
+                            }
+                        }
+                        break;
+                    }
+                    watched = values;
+                }
+                show_current(&process);
+            }
+            "b" | "break" => match arg(1) {
+                Some(addr) => {
+                    if breakpoints.remove(&addr) {
+                        println!("Breakpoint removed at {}", addr);
+                    } else {
+                        breakpoints.insert(addr);
+                        println!("Breakpoint set at {}", addr);
+                    }
+                }
+                None => println!("Usage: break ADDR"),
+            },
+            "w" | "watch" => match arg(1) {
+                Some(addr) if addr < process.mem().len() => {
+                    if watchpoints.remove(&addr) {
+                        println!("Watchpoint removed at {}", addr);
+                    } else {
+                        watchpoints.insert(addr);
+                        println!("Watchpoint set at {}", addr);
+                    }
+                }
+                Some(addr) => println!("Address {} is out of memory", addr),
+                None => println!("Usage: watch ADDR"),
+            },
+            "i" | "input" => {
+                for word in &words[1..] {
+                    match word.parse() {
+                        Ok(value) => process.input.push(value),
+                        Err(_) => println!("Bad input value '{}'", word),
+                    }
+                }
+            }
+            "r" | "regs" => {
+                println!("ip={} rb={}", process.ip(), process.rb());
+                println!("input={:?}", process.input);
+                println!("output={:?}", process.output);
+            }
+            "bt" | "backtrace" => print!("{}", stack.backtrace(&process)),
+            "x" => match arg(1) {
+                Some(addr) => {
+                    let count = arg(2).unwrap_or(8);
+                    let cells = process.mem().iter().enumerate().skip(addr);
+                    for (a, value) in cells.take(count) {
+                        println!("{:>5}: {}", a, value);
+                    }
+                }
+                None => println!("Usage: x ADDR [N]"),
+            },
+            "set" => {
+                match (arg(1), words.get(2).and_then(|w| w.parse().ok())) {
+                    (Some(addr), Some(value)) if addr < process.mem().len() => {
+                        process.poke(addr, value);
+                        println!("{:>5}: {}", addr, value);
+                    }
+                    (Some(addr), Some(_)) => {
+                        println!("Address {} is out of memory", addr)
+                    }
+                    _ => println!("Usage: set ADDR VALUE"),
+                }
+            }
+            "snap" => {
+                snapshot = Snapshot::take(&process);
+                println!("Snapshot taken at ip={}", process.ip());
+            }
+            "diff" => {
+                let changes = snapshot.diff(&Snapshot::take(&process));
+                println!(
+                    "{} cells changed since ip={}:",
+                    changes.len(),
+                    snapshot.ip
+                );
+                for change in changes {
+                    println!("{}", change);
+                }
+            }
+            "l" | "list" => {
+                let mut addr = arg(1).unwrap_or_else(|| process.ip());
+                for _ in 0..10 {
+                    match Instruction::decode(process.mem(), addr) {
+                        Some(instr) => {
+                            println!("{:>5}: {}", addr, instr);
+                            addr += instr.size();
+                        }
+                        None => break,
+                    }
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            "q" | "quit" => break,
+            "" => (),
+            other => println!("Unknown command '{}'", other),
+        }
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Execute one instruction, reporting any events.
+///
+/// Returns `false` when execution cannot continue without user action.
+fn step(
+    process: &mut Process,
+    stack: &mut CallStack,
+    halted: &mut bool,
+) -> bool {
+    if let Err(fault) = process.check() {
+        println!("{}; fix it with 'set' to go on", fault);
+        return false;
+    }
+
+    match stack.step(process) {
+        None => true,
+        Some(Status::NewOutput) => {
+            for value in process.output.drain(..) {
+                println!("Output: {}", value);
+            }
+            true
+        }
+        Some(Status::WantInput) => {
+            println!("Program wants input; queue some with 'input'");
+            false
+        }
+        Some(Status::Halt) => {
+            println!("Program halted");
+            *halted = true;
+            false
+        }
+    }
+}
+
+/// Current values of the watched addresses, sorted by address.
+fn watch_values(
+    process: &Process,
+    watchpoints: &HashSet<usize>,
+) -> Vec<(usize, Intcode)> {
+    let mut values: Vec<_> = watchpoints
+        .iter()
+        .map(|addr| (*addr, process.mem()[*addr]))
+        .collect();
+    values.sort_unstable();
+    values
+}
+
+fn show_current(process: &Process) {
+    match Instruction::decode(process.mem(), process.ip()) {
+        Some(instr) => println!("=> {:>5}: {}", process.ip(), instr),
+        None => println!("=> {:>5}: (invalid instruction)", process.ip()),
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Instant;
+
+use crate::intcode::{Device, Intcode};
+
+// Example devices, to be mapped into the memory of a `Process`.
+
+// ----------------------------------------------------------------------------
+
+/// Grid of cells that a program can paint into, one memory cell per tile.
+///
+/// Cells are laid out in row order: the tile at `(x, y)` is found at offset
+/// `y * width + x` from the start of the mapped range.
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<Intcode>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    /// Number of memory cells taken by the device.
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Intcode {
+        self.cells[y * self.width + x]
+    }
+
+    /// Draw the grid as text, with a character for each tile value.
+    pub fn render(&self, glyphs: &[char]) -> String {
+        let mut result = String::new();
+
+        for row in self.cells.chunks(self.width) {
+            for value in row {
+                result.push(*glyphs.get(*value as usize).unwrap_or(&'?'));
+            }
+            result.push('\n');
+        }
+
+        result
+    }
+}
+
+impl Device for Framebuffer {
+    fn read(&mut self, offset: usize) -> Intcode {
+        self.cells[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: Intcode) {
+        self.cells[offset] = value;
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Timer register: reads give the milliseconds elapsed since the timer was
+/// created or last reset. Writing any value resets it.
+pub struct Timer {
+    start: Instant,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Timer {
+        Timer::new()
+    }
+}
+
+impl Device for Timer {
+    fn read(&mut self, _offset: usize) -> Intcode {
+        self.start.elapsed().as_millis() as Intcode
+    }
+
+    fn write(&mut self, _offset: usize, _value: Intcode) {
+        self.start = Instant::now();
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{Process, Status};
+    use std::sync::{Arc, Mutex};
+
+    fn run(process: &mut Process) {
+        while process.exec() != Status::Halt {}
+    }
+
+    #[test]
+    fn framebuffer_writes() {
+        // Paint tiles 1 and 2 at (1, 0) and (0, 1), in position and relative
+        // modes; then read back (1, 0) and output it.
+        let program = [
+            1101, 0, 1, 1001, // [1001] = 1
+            109, 1000, // rb = 1000
+            21101, 0, 2, 2, // [rb+2] = 2
+            4, 1001, // Output [1001]
+            99,
+        ];
+        let fb = Arc::new(Mutex::new(Framebuffer::new(2, 2)));
+
+        let mut process = Process::new(&program);
+        process.map(1000..1004, Box::new(fb.clone()));
+        run(&mut process);
+
+        let fb = fb.lock().unwrap();
+        assert_eq!(fb.render(&[' ', '#', '*']), " #\n* \n");
+        assert_eq!(process.output, [1]);
+        assert_eq!(process.mem()[1001], 0); // Plain memory is untouched
+    }
+
+    #[test]
+    fn timer_reads() {
+        // Reset the timer, then output its value
+        let program = [1101, 0, 0, 2000, 4, 2000, 99];
+
+        let mut process = Process::new(&program);
+        process.map(2000..2001, Box::new(Timer::new()));
+        run(&mut process);
+
+        assert_eq!(process.output.len(), 1);
+        assert!(process.output[0] < 1000);
+    }
+
+    #[test]
+    fn code_in_mapped_range() {
+        // Immediate operands are part of the instruction, so they're taken
+        // from plain memory even where a device is mapped
+        let program = [1101, 2, 3, 20, 4, 20, 99];
+
+        let mut process = Process::new(&program);
+        process.map(0..4, Box::new(Framebuffer::new(4, 1)));
+        run(&mut process);
+
+        assert_eq!(process.output, [5]);
+    }
+
+    #[test]
+    fn unmapped_addresses() {
+        let program = [1101, 3, 4, 10, 4, 10, 99];
+
+        let mut process = Process::new(&program);
+        process.map(100..200, Box::new(Framebuffer::new(10, 10)));
+        run(&mut process);
+
+        assert_eq!(process.output, [7]);
+    }
+}
@@ -0,0 +1,112 @@
+use num_traits::FromPrimitive;
+use std::fmt;
+
+use crate::intcode::{Intcode, MemMode, Opcode};
+
+// ----------------------------------------------------------------------------
+
+/// A decoded instruction, as found at some address in memory.
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub params: Vec<(MemMode, Intcode)>,
+}
+
+impl Instruction {
+    /// Decode the instruction at `addr`.
+    ///
+    /// Returns `None` if the value is not a valid opcode, or if its
+    /// parameters don't fit in `mem`.
+    pub fn decode(mem: &[Intcode], addr: usize) -> Option<Instruction> {
+        let intcode = *mem.get(addr)?;
+        if intcode < 0 {
+            return None;
+        }
+        let opcode = Opcode::from_i64(intcode % 100)?;
+
+        let count = opcode.param_count();
+        if addr + count >= mem.len() {
+            return None;
+        }
+
+        let mut params = Vec::with_capacity(count);
+        for i in 0..count {
+            let m = (intcode / (10 as Intcode).pow(i as u32 + 2)) % 10;
+            params.push((MemMode::from_i64(m)?, mem[addr + 1 + i]));
+        }
+
+        // Reject unused mode digits, so data isn't mistaken for code
+        if intcode / (10 as Intcode).pow(count as u32 + 2) != 0 {
+            return None;
+        }
+
+        Some(Instruction { opcode, params })
+    }
+
+    /// Number of memory cells taken by this instruction.
+    pub fn size(&self) -> usize {
+        1 + self.params.len()
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<4}", format!("{:?}", self.opcode))?;
+
+        for (i, (mode, value)) in self.params.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            match mode {
+                MemMode::Position => write!(f, "{}[{}]", sep, value)?,
+                MemMode::Immediate => write!(f, "{}{}", sep, value)?,
+                MemMode::Relative => write!(f, "{}[rb{:+}]", sep, value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Linear sweep disassembly of a whole program.
+///
+/// Values that don't decode as an instruction are listed as data.
+pub fn disassemble(program: &[Intcode]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+
+    while addr < program.len() {
+        match Instruction::decode(program, addr) {
+            Some(instr) => {
+                lines.push(format!("{:>5}: {}", addr, instr));
+                addr += instr.size();
+            }
+            None => {
+                lines.push(format!("{:>5}: DATA {}", addr, program[addr]));
+                addr += 1;
+            }
+        }
+    }
+
+    lines
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_modes() {
+        let instr = Instruction::decode(&[21101, 3, -4, 7], 0).unwrap();
+        assert_eq!(instr.opcode, Opcode::ADD);
+        assert_eq!(instr.size(), 4);
+        assert_eq!(instr.to_string(), "ADD  3, -4, [rb+7]");
+    }
+
+    #[test]
+    fn disassemble_data() {
+        let lines = disassemble(&[104, 42, 99, 7]);
+        assert_eq!(lines, ["    0: OUT  42", "    2: HALT", "    3: DATA 7"]);
+    }
+}
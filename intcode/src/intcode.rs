@@ -0,0 +1,533 @@
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+pub type Intcode = i64;
+
+pub struct Process {
+    pub input: Vec<Intcode>,  // Queue of input values
+    pub output: Vec<Intcode>, // Queue of output values
+
+    mem: Vec<Intcode>, // Each process has its own memory
+    ip: Cell<usize>,   // Instruction Pointer, keeps track of execution
+    rb: usize,         // Relative base
+
+    devices: Vec<(Range<usize>, Box<dyn Device + Send>)>, // Mapped I/O
+    mmio: Range<usize>, // Span of all mapped ranges, for a quick check
+}
+
+/// A host device, backing a range of memory addresses in a `Process`.
+///
+/// Reads and writes done by instruction parameters within the mapped range
+/// are forwarded to the device, with `offset` counting from the start of the
+/// range. Instructions themselves, immediate operands included, are always
+/// fetched from plain memory.
+pub trait Device {
+    fn read(&mut self, offset: usize) -> Intcode;
+    fn write(&mut self, offset: usize, value: Intcode);
+}
+
+// Allows the host to keep a handle to a device, after mapping a clone of it
+impl<D: Device> Device for Arc<Mutex<D>> {
+    fn read(&mut self, offset: usize) -> Intcode {
+        self.lock().expect("Device lock").read(offset)
+    }
+
+    fn write(&mut self, offset: usize, value: Intcode) {
+        self.lock().expect("Device lock").write(offset, value)
+    }
+}
+
+/// Why `exec()` stopped running a process. This is the whole contract for
+/// suspending and resuming it:
+///
+/// - `NewOutput`: an `OUT` instruction pushed one value to `output`, and the
+///   ip points at the next instruction.
+/// - `WantInput`: an `IN` instruction found `input` empty. The ip still points
+///   at it, so it runs again, reading the value, once input has been pushed.
+/// - `Halt`: the ip stays at the `HALT` instruction, so running the process
+///   again just halts again.
+///
+/// In every case, calling `exec()` again resumes exactly where it stopped,
+/// without losing or repeating any instruction.
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    WantInput,
+    NewOutput,
+    Halt,
+}
+
+/// What `exec_chunk()` stopped with.
+#[derive(Debug, PartialEq)]
+pub enum Chunk<const N: usize> {
+    /// `N` consecutive output values.
+    Output([Intcode; N]),
+    /// The process wants input; outputs of an incomplete chunk stay queued.
+    WantInput,
+    /// The process halted, with no outputs left over.
+    Halt,
+}
+
+/// The process halted in the middle of a chunk, leaving these outputs.
+#[derive(Debug, PartialEq)]
+pub struct PartialChunk(pub Vec<Intcode>);
+
+/// Instruction that a process can't execute, as found by `Process::check()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fault {
+    pub addr: usize,
+    pub word: Intcode,
+}
+
+/// Iterator over chunks of outputs, see `Process::chunks()`.
+pub struct Chunks<'a, const N: usize> {
+    process: &'a mut Process,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive)]
+pub enum Opcode {
+    ADD = 1,   // Addition
+    MUL = 2,   // Multiplication
+    IN = 3,    // Read input
+    OUT = 4,   // Write output
+    JIT = 5,   // Jump if true
+    JIF = 6,   // Jump if false
+    LT = 7,    // Less than
+    EQ = 8,    // Equal
+    RBO = 9,   // Relative base offset
+    HALT = 99, // End of program
+}
+
+// Memory access modes
+#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive)]
+pub enum MemMode {
+    Position = 0,  // Read, Write
+    Immediate = 1, // Read only
+    Relative = 2,  // Read, Write
+}
+
+impl Opcode {
+    /// Number of parameters that follow the opcode in memory.
+    pub fn param_count(self) -> usize {
+        match self {
+            Opcode::ADD | Opcode::MUL | Opcode::LT | Opcode::EQ => 3,
+            Opcode::JIT | Opcode::JIF => 2,
+            Opcode::IN | Opcode::OUT | Opcode::RBO => 1,
+            Opcode::HALT => 0,
+        }
+    }
+}
+
+impl Process {
+    pub fn new(program: &[Intcode]) -> Process {
+        let mut mem = program.to_vec();
+        mem.resize(4096, 0); // Ensure 4KB memory size
+
+        Process {
+            input: Vec::new(),
+            output: Vec::new(),
+            mem,
+            ip: Cell::new(0),
+            rb: 0,
+            devices: Vec::new(),
+            mmio: 0..0,
+        }
+    }
+
+    /// Map a device into the given range of memory addresses.
+    pub fn map(&mut self, range: Range<usize>, device: Box<dyn Device + Send>) {
+        assert!(range.start < range.end, "Empty device range");
+        assert!(range.end <= self.mem.len(), "Device range out of memory");
+        for (r, _) in &self.devices {
+            if range.start < r.end && r.start < range.end {
+                panic!("Device range {:?} overlaps {:?}", range, r);
+            }
+        }
+
+        if self.devices.is_empty() {
+            self.mmio = range.clone();
+        } else {
+            self.mmio.start = self.mmio.start.min(range.start);
+            self.mmio.end = self.mmio.end.max(range.end);
+        }
+        self.devices.push((range, device));
+    }
+
+    pub fn exec(&mut self) -> Status {
+        loop {
+            if let Some(status) = self.step() {
+                return status;
+            }
+        }
+    }
+
+    /// Run until the next output, and take that value from `output`.
+    ///
+    /// Returns `None` if the process halts or wants input first.
+    pub fn run_until_output(&mut self) -> Option<Intcode> {
+        match self.exec() {
+            Status::NewOutput => Some(self.output.remove(0)),
+            _ => None,
+        }
+    }
+
+    /// Run until the process wants input or halts, leaving all outputs in
+    /// `output`. Returns which of the two happened.
+    pub fn run_until_input(&mut self) -> Status {
+        loop {
+            match self.exec() {
+                Status::NewOutput => continue,
+                status => return status,
+            }
+        }
+    }
+
+    /// Run until the process halts, leaving all outputs in `output`.
+    ///
+    /// Panics if the process wants more input than there is in `input`.
+    pub fn run_until_halt(&mut self) {
+        if self.run_until_input() == Status::WantInput {
+            panic!("Input underflow");
+        }
+    }
+
+    /// Run the process to completion with the given inputs, and take all of
+    /// its outputs.
+    pub fn run_to_completion(&mut self, inputs: &[Intcode]) -> Vec<Intcode> {
+        self.input.extend_from_slice(inputs);
+        self.run_until_halt();
+        self.output.drain(..).collect()
+    }
+
+    /// Run until `N` outputs are queued, and take them as a single chunk.
+    ///
+    /// Returns an error if the process halts with fewer than `N` outputs
+    /// queued, so that partial chunks are never silently dropped.
+    pub fn exec_chunk<const N: usize>(
+        &mut self,
+    ) -> Result<Chunk<N>, PartialChunk> {
+        while self.output.len() < N {
+            match self.exec() {
+                Status::NewOutput => (),
+                Status::WantInput => return Ok(Chunk::WantInput),
+                Status::Halt if self.output.is_empty() => {
+                    return Ok(Chunk::Halt)
+                }
+                Status::Halt => {
+                    return Err(PartialChunk(self.output.drain(..).collect()))
+                }
+            }
+        }
+
+        let mut chunk = [0; N];
+        chunk.copy_from_slice(&self.output[..N]);
+        self.output.drain(..N);
+        Ok(Chunk::Output(chunk))
+    }
+
+    /// Iterate over chunks of `N` outputs, until the process halts or wants
+    /// input.
+    pub fn chunks<const N: usize>(&mut self) -> Chunks<'_, N> {
+        Chunks { process: self }
+    }
+
+    /// Check that the instruction at the ip can be executed: that its opcode
+    /// is valid, and so are the modes of its parameters. `step()` and `exec()`
+    /// panic on any that isn't.
+    pub fn check(&self) -> Result<(), Fault> {
+        let addr = self.ip();
+        let word = self.mem.get(addr).copied().unwrap_or(0);
+        let fault = Fault { addr, word };
+
+        let opcode = Opcode::from_i64(word % 100).ok_or(fault)?;
+        for i in 0..opcode.param_count() {
+            let m = (word / (10 as Intcode).pow(i as u32 + 2)) % 10;
+            let mode = MemMode::from_i64(m).ok_or(fault)?;
+
+            let writes = match opcode {
+                Opcode::IN => i == 0,
+                Opcode::ADD | Opcode::MUL | Opcode::LT | Opcode::EQ => i == 2,
+                _ => false,
+            };
+            if writes && mode == MemMode::Immediate {
+                return Err(fault);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Execute a single instruction.
+    ///
+    /// Returns `None` if execution can continue, or the `Status` that
+    /// `exec()` would have stopped with.
+    pub fn step(&mut self) -> Option<Status> {
+        let intcode = *self.mem.get(self.next_ip()).expect("Bad address");
+        let opcode = Opcode::from_i64(intcode % 100).expect("Bad Opcode");
+
+        match opcode {
+            Opcode::ADD => {
+                let pmodes = Process::get_param_modes(&intcode, 3);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                let p1 = self.mem_read(self.next_ip(), &pmodes[1]);
+                self.mem_write(self.next_ip(), &pmodes[2], p0 + p1);
+            }
+            Opcode::MUL => {
+                let pmodes = Process::get_param_modes(&intcode, 3);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                let p1 = self.mem_read(self.next_ip(), &pmodes[1]);
+                self.mem_write(self.next_ip(), &pmodes[2], p0 * p1);
+            }
+            Opcode::IN => {
+                if self.input.is_empty() {
+                    self.revert_ip();
+                    return Some(Status::WantInput);
+                }
+
+                let pmodes = Process::get_param_modes(&intcode, 1);
+                let input = self.input.remove(0);
+                self.mem_write(self.next_ip(), &pmodes[0], input);
+            }
+            Opcode::OUT => {
+                let pmodes = Process::get_param_modes(&intcode, 1);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                self.output.push(p0);
+                return Some(Status::NewOutput);
+            }
+            Opcode::JIT => {
+                let pmodes = Process::get_param_modes(&intcode, 2);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                let p1 = self.mem_read(self.next_ip(), &pmodes[1]);
+                if p0 != 0 {
+                    self.ip.set(p1 as usize);
+                }
+            }
+            Opcode::JIF => {
+                let pmodes = Process::get_param_modes(&intcode, 2);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                let p1 = self.mem_read(self.next_ip(), &pmodes[1]);
+                if p0 == 0 {
+                    self.ip.set(p1 as usize);
+                }
+            }
+            Opcode::LT => {
+                let pmodes = Process::get_param_modes(&intcode, 3);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                let p1 = self.mem_read(self.next_ip(), &pmodes[1]);
+                if p0 < p1 {
+                    self.mem_write(self.next_ip(), &pmodes[2], 1);
+                } else {
+                    self.mem_write(self.next_ip(), &pmodes[2], 0);
+                }
+            }
+            Opcode::EQ => {
+                let pmodes = Process::get_param_modes(&intcode, 3);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                let p1 = self.mem_read(self.next_ip(), &pmodes[1]);
+                if p0 == p1 {
+                    self.mem_write(self.next_ip(), &pmodes[2], 1);
+                } else {
+                    self.mem_write(self.next_ip(), &pmodes[2], 0);
+                }
+            }
+            Opcode::RBO => {
+                let pmodes = Process::get_param_modes(&intcode, 1);
+                let p0 = self.mem_read(self.next_ip(), &pmodes[0]);
+                self.rb = (self.rb as i64 + p0) as usize;
+            }
+            Opcode::HALT => {
+                self.revert_ip();
+                return Some(Status::Halt);
+            }
+        }
+
+        None
+    }
+
+    // Introspection, used by the debugging tools
+
+    pub fn ip(&self) -> usize {
+        self.ip.get()
+    }
+
+    pub fn rb(&self) -> usize {
+        self.rb
+    }
+
+    pub fn mem(&self) -> &[Intcode] {
+        &self.mem
+    }
+
+    /// Overwrite a memory cell, bypassing any mapped device.
+    pub fn poke(&mut self, addr: usize, value: Intcode) {
+        self.mem[addr] = value;
+    }
+
+    fn get_param_modes(intcode: &Intcode, count: u32) -> Vec<MemMode> {
+        (0..count)
+            .map(|i| {
+                let m = (intcode / ((10 as Intcode).pow(i + 2))) % 10;
+                MemMode::from_i64(m).expect("Bad MemMode")
+            })
+            .collect()
+    }
+
+    fn mem_read(&mut self, index: usize, mode: &MemMode) -> Intcode {
+        let addr = match mode {
+            MemMode::Position => self.mem[index] as usize,
+            // Part of the instruction, so never from a device
+            MemMode::Immediate => return self.mem[index],
+            MemMode::Relative => {
+                (self.rb as Intcode + self.mem[index]) as usize
+            }
+        };
+
+        if self.mmio.contains(&addr) {
+            if let Some((range, device)) = self.device_at(addr) {
+                return device.read(addr - range.start);
+            }
+        }
+
+        self.mem[addr]
+    }
+
+    fn mem_write(&mut self, index: usize, mode: &MemMode, value: Intcode) {
+        let addr = match mode {
+            MemMode::Position => self.mem[index] as usize,
+            MemMode::Relative => {
+                (self.rb as Intcode + self.mem[index]) as usize
+            }
+            _ => panic!("Bad write mode"),
+        };
+
+        if self.mmio.contains(&addr) {
+            if let Some((range, device)) = self.device_at(addr) {
+                return device.write(addr - range.start, value);
+            }
+        }
+
+        self.mem[addr] = value;
+    }
+
+    fn device_at(
+        &mut self,
+        addr: usize,
+    ) -> Option<&mut (Range<usize>, Box<dyn Device + Send>)> {
+        self.devices
+            .iter_mut()
+            .find(|(range, _)| range.contains(&addr))
+    }
+
+    fn next_ip(&self) -> usize {
+        let aux = self.ip.get();
+        self.ip.set(self.ip.get() + 1);
+        aux
+    }
+
+    fn revert_ip(&self) {
+        self.ip.set(self.ip.get() - 1);
+    }
+}
+
+impl<'a, const N: usize> Iterator for Chunks<'a, N> {
+    type Item = Result<[Intcode; N], PartialChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.process.exec_chunk() {
+            Ok(Chunk::Output(chunk)) => Some(Ok(chunk)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid instruction {} at address {}",
+            self.word, self.addr
+        )
+    }
+}
+
+impl Error for Fault {}
+
+impl fmt::Display for PartialChunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Program halted with partial output {:?}", self.0)
+    }
+}
+
+impl Error for PartialChunk {}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn output_chunks() {
+        // Output pairs (1, 2) and (3, 4), then a lone 5
+        let program = [104, 1, 104, 2, 104, 3, 104, 4, 104, 5, 99];
+        let mut process = Process::new(&program);
+        let chunks: Vec<_> = process.chunks::<2>().collect();
+        assert_eq!(
+            chunks,
+            [Ok([1, 2]), Ok([3, 4]), Err(PartialChunk(vec![5]))]
+        );
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::Halt));
+
+        // Chunks can span input requests
+        let program = [104, 1, 3, 9, 4, 9, 99, 0, 0, 0];
+        let mut process = Process::new(&program);
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::WantInput));
+        process.input.push(7);
+        assert_eq!(process.exec_chunk::<2>(), Ok(Chunk::Output([1, 7])));
+    }
+
+    #[test]
+    fn check_instructions() {
+        let mut process = Process::new(&[1101, 1, 1, 5, 77, 99]);
+        assert_eq!(process.check(), Ok(()));
+        assert_eq!(process.step(), None);
+        assert_eq!(process.check(), Err(Fault { addr: 4, word: 77 }));
+
+        // Immediate mode for a parameter written to, and an unknown mode
+        for word in &[11101, 103, 301] {
+            assert!(Process::new(&[*word, 0, 0, 0]).check().is_err());
+        }
+    }
+
+    #[test]
+    fn run_variants() {
+        // Output the input plus one, twice
+        let program = [3, 13, 1001, 13, 1, 13, 4, 13, 1105, 1, 0, 99, 99, 0];
+
+        let mut process = Process::new(&program);
+        assert_eq!(process.run_until_output(), None);
+        process.input.push(1);
+        assert_eq!(process.run_until_output(), Some(2));
+        assert_eq!(process.run_until_input(), Status::WantInput);
+        process.input.extend_from_slice(&[5, 7]);
+        assert_eq!(process.run_until_input(), Status::WantInput);
+        assert_eq!(process.output, [6, 8]);
+
+        // Outputs left over are taken first
+        process.input.push(9);
+        assert_eq!(process.run_until_output(), Some(6));
+        assert_eq!(process.output, [8, 10]);
+
+        let mut process = Process::new(&program);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            process.run_to_completion(&[1])
+        }));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,13 @@
+// Intcode virtual machine, shared by the command-line tool and by any other
+// crate that wants to run Intcode programs.
+
+mod intcode;
+pub use crate::intcode::*;
+
+pub mod async_process;
+pub mod devices;
+pub mod disasm;
+pub mod loader;
+pub mod memdiff;
+pub mod stack;
+pub mod threaded;
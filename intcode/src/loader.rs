@@ -0,0 +1,392 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::intcode::Intcode;
+
+// Intcode programs can be loaded from two formats:
+//
+// * Text: comma-separated integers, as given by the puzzles. Values can also
+//   be split across lines, with or without a trailing comma, whitespace is
+//   allowed between values, and `#` starts a comment until the end of line.
+//
+// * Binary: the `MAGIC` bytes, followed by the number of values and then the
+//   values themselves, all encoded as zigzag LEB128 varints, and finally the
+//   checksum of the values as 8 little-endian bytes.
+
+pub const MAGIC: &[u8; 4] = b"ICB1";
+
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    /// Text format error, at the given line and column (both 1-based).
+    Syntax {
+        line: usize,
+        column: usize,
+        msg: String,
+    },
+    /// Binary format error, at the given byte offset.
+    Binary { offset: usize, msg: String },
+    /// The binary checksum doesn't match its contents.
+    Checksum { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Syntax { line, column, msg } => {
+                write!(f, "{}:{}: {}", line, column, msg)
+            }
+            LoadError::Binary { offset, msg } => {
+                write!(f, "byte {}: {}", offset, msg)
+            }
+            LoadError::Checksum { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {:016x}, found {:016x}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+// ----------------------------------------------------------------------------
+
+/// Load a program, in either text or binary format.
+pub fn load(data: &[u8]) -> Result<Vec<Intcode>, LoadError> {
+    if data.starts_with(MAGIC) {
+        decode(data)
+    } else {
+        parse(data)
+    }
+}
+
+/// FNV-1a hash of the program values, used to fingerprint programs.
+pub fn checksum(program: &[Intcode]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for value in program {
+        for byte in value.to_le_bytes().iter() {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    hash
+}
+
+// ----------------------------------------------------------------------------
+
+/// Parse a program in text format.
+pub fn parse(text: &[u8]) -> Result<Vec<Intcode>, LoadError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        line: 1,
+        line_start: 0,
+    };
+    let mut program = Vec::with_capacity(text.len() / 2);
+
+    // A value is required after each comma, except at the end of a line
+    let mut need_value = false;
+
+    loop {
+        parser.skip_blank(!need_value);
+
+        match parser.peek() {
+            None if need_value => return Err(parser.error("expected a value")),
+            None => break,
+            Some(b',') => return Err(parser.error("expected a value")),
+            Some(_) => program.push(parser.number()?),
+        }
+
+        parser.skip_blank(false);
+
+        match parser.peek() {
+            Some(b',') => {
+                parser.pos += 1;
+                need_value = true;
+
+                // Accept a trailing comma at the end of a line
+                parser.skip_blank(false);
+                if let None | Some(b'\n') = parser.peek() {
+                    need_value = false;
+                }
+            }
+            Some(b'\n') | None => need_value = false,
+            Some(_) => return Err(parser.error("expected ',' or end of line")),
+        }
+    }
+
+    if program.is_empty() {
+        return Err(parser.error("empty program"));
+    }
+
+    Ok(program)
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    line: usize,
+    line_start: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    /// Skip spaces and comments, and also line breaks if `newlines` is set.
+    fn skip_blank(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                b'#' => {
+                    while let Some(c) = self.peek() {
+                        if c == b'\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                b'\n' if newlines => {
+                    self.pos += 1;
+                    self.line += 1;
+                    self.line_start = self.pos;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Intcode, LoadError> {
+        let start = self.pos;
+        let negative = self.peek() == Some(b'-');
+        if negative || self.peek() == Some(b'+') {
+            self.pos += 1;
+        }
+
+        let mut value: Intcode = 0;
+        let mut digits = 0;
+
+        while let Some(c @ b'0'..=b'9') = self.peek() {
+            let digit = Intcode::from(c - b'0');
+            value = value
+                .checked_mul(10)
+                .and_then(|v| {
+                    if negative {
+                        v.checked_sub(digit)
+                    } else {
+                        v.checked_add(digit)
+                    }
+                })
+                .ok_or_else(|| self.error_at(start, "number out of range"))?;
+            digits += 1;
+            self.pos += 1;
+        }
+
+        if digits == 0 || !self.at_separator() {
+            while !self.at_separator() {
+                self.pos += 1;
+            }
+            let token = String::from_utf8_lossy(&self.text[start..self.pos]);
+            let msg = format!("invalid number '{}'", token);
+            return Err(self.error_at(start, &msg));
+        }
+
+        Ok(value)
+    }
+
+    fn at_separator(&self) -> bool {
+        match self.peek() {
+            Some(c) => b",# \t\r\n".contains(&c),
+            None => true,
+        }
+    }
+
+    fn error(&self, msg: &str) -> LoadError {
+        self.error_at(self.pos, msg)
+    }
+
+    fn error_at(&self, pos: usize, msg: &str) -> LoadError {
+        LoadError::Syntax {
+            line: self.line,
+            column: pos - self.line_start + 1,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Encode a program in binary format.
+pub fn encode(program: &[Intcode]) -> Vec<u8> {
+    let mut data = MAGIC.to_vec();
+
+    write_varint(&mut data, program.len() as Intcode);
+    for value in program {
+        write_varint(&mut data, *value);
+    }
+    data.extend_from_slice(&checksum(program).to_le_bytes());
+
+    data
+}
+
+/// Decode a program in binary format.
+pub fn decode(data: &[u8]) -> Result<Vec<Intcode>, LoadError> {
+    if !data.starts_with(MAGIC) {
+        return Err(LoadError::Binary {
+            offset: 0,
+            msg: String::from("bad magic bytes"),
+        });
+    }
+    let mut pos = MAGIC.len();
+
+    let count = read_varint(data, &mut pos)?;
+    if count < 0 || count as usize > data.len() {
+        return Err(LoadError::Binary {
+            offset: MAGIC.len(),
+            msg: format!("bad value count {}", count),
+        });
+    }
+
+    let mut program = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        program.push(read_varint(data, &mut pos)?);
+    }
+
+    let mut expected = [0; 8];
+    match data.get(pos..) {
+        Some(rest) if rest.len() == expected.len() => {
+            expected.copy_from_slice(rest)
+        }
+        _ => {
+            return Err(LoadError::Binary {
+                offset: pos,
+                msg: String::from("missing checksum"),
+            })
+        }
+    }
+    let expected = u64::from_le_bytes(expected);
+    let actual = checksum(&program);
+    if expected != actual {
+        return Err(LoadError::Checksum { expected, actual });
+    }
+
+    Ok(program)
+}
+
+fn write_varint(data: &mut Vec<u8>, value: Intcode) {
+    // Zigzag, so small negative numbers take few bytes too
+    let mut v = ((value << 1) ^ (value >> 63)) as u64;
+
+    while v >= 0x80 {
+        data.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    data.push(v as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<Intcode, LoadError> {
+    let start = *pos;
+    let mut v: u64 = 0;
+
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or_else(|| LoadError::Binary {
+            offset: start,
+            msg: String::from("truncated value"),
+        })?;
+        *pos += 1;
+
+        v |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(((v >> 1) as Intcode) ^ -((v & 1) as Intcode));
+        }
+    }
+
+    Err(LoadError::Binary {
+        offset: start,
+        msg: String::from("value too long"),
+    })
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_error(text: &str) -> (usize, usize, String) {
+        match parse(text.as_bytes()) {
+            Err(LoadError::Syntax { line, column, msg }) => (line, column, msg),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_puzzle_format() {
+        assert_eq!(parse(b"1,0,0,3,99\n"), Ok(vec![1, 0, 0, 3, 99]));
+        assert_eq!(
+            parse(b"104,-1125899906842624,99"),
+            Ok(vec![104, -1125899906842624, 99])
+        );
+    }
+
+    #[test]
+    fn parse_multiline_with_comments() {
+        let text = "# Add two numbers\n\
+                    1101, 2, 3, 7,   # [7] = 2 + 3\n\
+                    \n\
+                    4, 7,\r\n\
+                    99, 0\n";
+        assert_eq!(
+            parse(text.as_bytes()),
+            Ok(vec![1101, 2, 3, 7, 4, 7, 99, 0])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let (line, column, msg) = syntax_error("1,2,\n3,x4,99");
+        assert_eq!((line, column), (2, 3));
+        assert_eq!(msg, "invalid number 'x4'");
+
+        assert_eq!(syntax_error("1,,2").1, 3);
+        assert_eq!(syntax_error("1 2").2, "expected ',' or end of line");
+        assert_eq!(
+            syntax_error("99999999999999999999").2,
+            "number out of range"
+        );
+        assert_eq!(syntax_error("# nothing\n").2, "empty program");
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let program =
+            vec![109, 1, 204, -1, 1001, 100, 1, 100, 99, Intcode::MIN];
+        let data = encode(&program);
+
+        assert!(data.len() < 4 + 1 + 8 * program.len());
+        assert_eq!(load(&data), Ok(program));
+    }
+
+    #[test]
+    fn binary_errors() {
+        let mut data = encode(&[1, 2, 3]);
+
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        match decode(&data) {
+            Err(LoadError::Checksum { .. }) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        data.truncate(6);
+        match decode(&data) {
+            Err(LoadError::Binary { offset: 6, .. }) => (),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use intcode::disasm::{self, Instruction};
+use intcode::loader;
+use intcode::memdiff::Snapshot;
+use intcode::stack::CallStack;
+use intcode::*;
+use num_traits::FromPrimitive;
+
+// ----------------------------------------------------------------------------
+
+mod debugger;
+
+const USAGE: &str = "\
+Usage: intcode <COMMAND> [--ascii] [--patch ADDR=VALUE]... <FILE> [INPUT...]
+
+Commands:
+  run       Run the program and print its outputs
+  disasm    Print a disassembly listing of the program
+  trace     Run the program, printing each instruction as it executes
+  profile   Run the program and report instruction counts
+  debug     Run the program in an interactive debugger
+  diff      Run the program with and without patches, and compare memory
+  info      Print the program size and checksum
+  pack      Save the program in binary format: pack <FILE> <OUTPUT>
+  unpack    Save the program in text format: unpack <FILE> <OUTPUT>
+
+Programs are loaded from text or binary format. Text programs are comma-
+separated, can span multiple lines, and can have '#' comments.
+
+Inputs are taken from the command line; once they run out, more are read
+from stdin, one line at a time. With --ascii, each input is a line of text,
+and outputs are printed as ASCII characters.
+
+With --patch, the program is changed before running it, by writing VALUE at
+address ADDR. This can be given more than once.";
+
+// ----------------------------------------------------------------------------
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = args.first().map(String::as_str).unwrap_or("");
+    let mut ascii = false;
+    let mut patches = Vec::new();
+    let mut rest = Vec::new();
+
+    let mut options = args.iter().skip(1);
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--ascii" => ascii = true,
+            "--patch" => match options.next().and_then(|p| parse_patch(p)) {
+                Some(patch) => patches.push(patch),
+                None => usage_error("Expected ADDR=VALUE after --patch"),
+            },
+            _ => rest.push(arg),
+        }
+    }
+    let mut args = rest.into_iter();
+
+    let path = match args.next() {
+        Some(path) if !command.is_empty() => path,
+        _ => usage_error("Required <COMMAND> and <FILE> are missing"),
+    };
+    let original = match loader::load(&fs::read(path)?) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("ERROR: {}:{}", path, err);
+            process::exit(1);
+        }
+    };
+    let program = patch(&original, &patches);
+
+    match command {
+        "info" => {
+            println!("Values: {}", program.len());
+            println!("Checksum: {:016x}", loader::checksum(&program));
+            return Ok(());
+        }
+        "pack" | "unpack" => {
+            let output = match args.next() {
+                Some(output) => output,
+                None => usage_error("Required <OUTPUT> is missing"),
+            };
+            let data = if command == "pack" {
+                loader::encode(&program)
+            } else {
+                let values: Vec<_> =
+                    program.iter().map(Intcode::to_string).collect();
+                format!("{}\n", values.join(",")).into_bytes()
+            };
+            return fs::write(output, data);
+        }
+        _ => (),
+    }
+
+    let mut inputs = Vec::new();
+    for arg in args {
+        inputs.extend(encode_input(arg, ascii)?);
+    }
+
+    match command {
+        "run" => run(&program, inputs, ascii),
+        "disasm" => {
+            for line in disasm::disassemble(&program) {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+        "trace" => trace(&program, inputs, ascii),
+        "profile" => profile(&program, inputs, ascii),
+        "debug" => debugger::debug(&program, inputs),
+        "diff" => diff(&original, &program, inputs),
+        _ => usage_error(&format!("Unknown command '{}'", command)),
+    }
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("ERROR: {}\n\n{}", msg, USAGE);
+    process::exit(1);
+}
+
+// ----------------------------------------------------------------------------
+
+/// Parse an `ADDR=VALUE` pair.
+fn parse_patch(arg: &str) -> Option<(usize, Intcode)> {
+    let mut parts = arg.splitn(2, '=');
+    let addr = parts.next()?.trim().parse().ok()?;
+    let value = parts.next()?.trim().parse().ok()?;
+    Some((addr, value))
+}
+
+/// Copy of `program` with the given values written into it, extended with
+/// zeros if needed.
+fn patch(program: &[Intcode], patches: &[(usize, Intcode)]) -> Vec<Intcode> {
+    let mut program = program.to_vec();
+
+    for &(addr, value) in patches {
+        if addr >= program.len() {
+            program.resize(addr + 1, 0);
+        }
+        program[addr] = value;
+    }
+
+    program
+}
+
+/// Convert one line of user input into Intcode input values.
+fn encode_input(line: &str, ascii: bool) -> io::Result<Vec<Intcode>> {
+    if ascii {
+        return Ok(line
+            .bytes()
+            .map(Intcode::from)
+            .chain(Some('\n' as Intcode))
+            .collect());
+    }
+
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse().map_err(|_| {
+                let msg = format!("Bad input value '{}'", s);
+                io::Error::new(io::ErrorKind::InvalidInput, msg)
+            })
+        })
+        .collect()
+}
+
+/// Refill the input queue from stdin.
+///
+/// Exits with an error if stdin is exhausted.
+fn read_input(process: &mut Process, ascii: bool) -> io::Result<()> {
+    let mut line = String::new();
+
+    while process.input.is_empty() {
+        line.clear();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            eprintln!("ERROR: Program wants input, but stdin is empty");
+            process::exit(1);
+        }
+        let line = line.trim_end_matches(&['\n', '\r'][..]);
+        process.input.extend(encode_input(line, ascii)?);
+    }
+
+    Ok(())
+}
+
+fn print_output(value: Intcode, ascii: bool) {
+    if ascii && (0..128).contains(&value) {
+        print!("{}", value as u8 as char);
+    } else {
+        println!("{}", value);
+    }
+}
+
+/// Exit with an error if the instruction at the ip can't be executed, instead
+/// of letting the VM panic on it.
+fn check(process: &Process) {
+    if let Err(fault) = process.check() {
+        eprintln!("ERROR: {}", fault);
+        process::exit(1);
+    }
+}
+
+/// Instruction at `addr`, or its raw value if it doesn't decode, like the
+/// disassembler does.
+fn describe(mem: &[Intcode], addr: usize) -> String {
+    match Instruction::decode(mem, addr) {
+        Some(instr) => instr.to_string(),
+        None => format!("DATA {}", mem[addr]),
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn run(
+    program: &[Intcode],
+    inputs: Vec<Intcode>,
+    ascii: bool,
+) -> io::Result<()> {
+    let mut process = Process::new(program);
+    process.input = inputs;
+
+    loop {
+        check(&process);
+        match process.step() {
+            None => (),
+            Some(Status::Halt) => break,
+            Some(Status::NewOutput) => {
+                for value in process.output.drain(..) {
+                    print_output(value, ascii);
+                }
+            }
+            Some(Status::WantInput) => {
+                io::stdout().flush()?;
+                read_input(&mut process, ascii)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+fn trace(
+    program: &[Intcode],
+    inputs: Vec<Intcode>,
+    ascii: bool,
+) -> io::Result<()> {
+    let mut process = Process::new(program);
+    process.input = inputs;
+    let mut stack = CallStack::new();
+
+    loop {
+        let ip = process.ip();
+        println!(
+            "{:>5}: {:<32} rb={}",
+            ip,
+            describe(process.mem(), ip),
+            process.rb()
+        );
+
+        check(&process);
+        let depth = stack.depth();
+        let status = stack.step(&mut process);
+        if stack.depth() > depth {
+            let frame = &stack.frames()[depth];
+            println!(
+                "       -- enter function {} ({})",
+                frame.function,
+                depth + 1
+            );
+        } else if stack.depth() < depth {
+            println!(
+                "       -- return to {} ({})",
+                process.ip(),
+                stack.depth()
+            );
+        }
+
+        match status {
+            None => (),
+            Some(Status::Halt) => break,
+            Some(Status::NewOutput) => {
+                for value in process.output.drain(..) {
+                    println!("       -> {}", value);
+                }
+            }
+            Some(Status::WantInput) => read_input(&mut process, ascii)?,
+        }
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+fn profile(
+    program: &[Intcode],
+    inputs: Vec<Intcode>,
+    ascii: bool,
+) -> io::Result<()> {
+    let mut process = Process::new(program);
+    process.input = inputs;
+
+    let mut total = 0;
+    let mut by_opcode = HashMap::new();
+    let mut by_addr = HashMap::new();
+    let mut modified = HashSet::new();
+
+    loop {
+        check(&process);
+
+        // The VM only looks at the opcode digits, so extra mode digits that
+        // keep an instruction from decoding don't stop it
+        let ip = process.ip();
+        let intcode = process.mem()[ip];
+        if let Some(opcode) = Opcode::from_i64(intcode % 100) {
+            *by_opcode.entry(opcode).or_insert(0) += 1;
+        }
+        *by_addr.entry(ip).or_insert(0) += 1;
+        if intcode != program.get(ip).copied().unwrap_or(0) {
+            modified.insert(ip);
+        }
+        total += 1;
+
+        match process.step() {
+            None => (),
+            Some(Status::Halt) => break,
+            Some(Status::NewOutput) => process.output.clear(),
+            Some(Status::WantInput) => read_input(&mut process, ascii)?,
+        }
+    }
+
+    println!("Instructions executed: {}", total);
+
+    println!("\nBy opcode:");
+    let mut by_opcode: Vec<_> = by_opcode.into_iter().collect();
+    by_opcode.sort_by_key(|&(opcode, count)| (-count, opcode as i64));
+    for (opcode, count) in by_opcode {
+        let percent = 100.0 * count as f64 / total as f64;
+        println!(
+            "  {:<4} {:>10} {:>6.2}%",
+            format!("{:?}", opcode),
+            count,
+            percent
+        );
+    }
+
+    println!("\nHottest instructions:");
+    let mut by_addr: Vec<_> = by_addr.into_iter().collect();
+    by_addr.sort_by_key(|&(addr, count)| (-count, addr));
+    for (addr, count) in by_addr.into_iter().take(10) {
+        // Code past the end of the program must have been written there
+        let instr = if modified.contains(&addr) {
+            String::from("(self-modified)")
+        } else {
+            describe(program, addr)
+        };
+        println!("  {:>5}: {:<32} {:>10}", addr, instr, count);
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+fn diff(
+    original: &[Intcode],
+    patched: &[Intcode],
+    inputs: Vec<Intcode>,
+) -> io::Result<()> {
+    if original == patched {
+        usage_error("Nothing to compare, use --patch to change the program");
+    }
+
+    let before = run_headless(original, inputs.clone(), "Original");
+    let after = run_headless(patched, inputs, "Patched");
+
+    let changes = before.diff(&after);
+    println!("\n{} cells differ:", changes.len());
+    for change in changes {
+        println!("{}", change);
+    }
+
+    Ok(())
+}
+
+/// Run a program with only the given inputs, and describe where it stopped.
+fn run_headless(
+    program: &[Intcode],
+    inputs: Vec<Intcode>,
+    name: &str,
+) -> Snapshot {
+    let mut process = Process::new(program);
+    process.input = inputs;
+    let mut outputs = 0;
+
+    let status = loop {
+        check(&process);
+        match process.step() {
+            None => (),
+            Some(Status::NewOutput) => {
+                outputs += process.output.len();
+                process.output.clear();
+            }
+            Some(status) => break status,
+        }
+    };
+
+    let state = match status {
+        Status::Halt => "halted",
+        _ => "waiting for input",
+    };
+    println!(
+        "{}: {} at ip={} rb={}, after {} outputs",
+        name,
+        state,
+        process.ip(),
+        process.rb(),
+        outputs
+    );
+
+    Snapshot::take(&process)
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use crate::intcode::{Intcode, Process};
+
+// Memory snapshots, and the tools to compare them.
+//
+// Programs keep their state in plain memory cells, with nothing to tell which
+// cell holds what. Comparing snapshots taken at chosen points, either in the
+// same run or in two runs of slightly different programs, narrows that down:
+// a cell that holds the score changes whenever a new score is output, and
+// holds the same value that was output.
+
+/// Copy of the registers and memory of a `Process` at some point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub ip: usize,
+    pub rb: usize,
+    pub mem: Vec<Intcode>,
+}
+
+/// Memory cell holding different values in two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Change {
+    pub addr: usize,
+    pub old: Intcode,
+    pub new: Intcode,
+}
+
+/// Search for the cells that hold some piece of state, narrowed down by
+/// checking each candidate against what's known about that state.
+#[derive(Debug, Clone)]
+pub struct Search {
+    candidates: Vec<usize>,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Snapshot {
+    pub fn take(process: &Process) -> Snapshot {
+        Snapshot {
+            ip: process.ip(),
+            rb: process.rb(),
+            mem: process.mem().to_vec(),
+        }
+    }
+
+    /// Value at `addr`, with memory beyond the snapshot reading as 0.
+    pub fn get(&self, addr: usize) -> Intcode {
+        self.mem.get(addr).copied().unwrap_or(0)
+    }
+
+    /// Cells that hold a different value in `other`, by address.
+    pub fn diff(&self, other: &Snapshot) -> Vec<Change> {
+        let len = self.mem.len().max(other.mem.len());
+
+        (0..len)
+            .filter_map(|addr| {
+                let (old, new) = (self.get(addr), other.get(addr));
+                if old != new {
+                    Some(Change { addr, old, new })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>5}: {} -> {}", self.addr, self.old, self.new)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Search {
+    /// Start with every cell in `snapshot` as a candidate.
+    pub fn new(snapshot: &Snapshot) -> Search {
+        Search {
+            candidates: (0..snapshot.mem.len()).collect(),
+        }
+    }
+
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+
+    /// Keep the cells holding `value` in `snapshot`.
+    pub fn equal(&mut self, snapshot: &Snapshot, value: Intcode) -> &mut Self {
+        self.candidates.retain(|addr| snapshot.get(*addr) == value);
+        self
+    }
+
+    /// Keep the cells that changed between `before` and `after`.
+    pub fn changed(
+        &mut self,
+        before: &Snapshot,
+        after: &Snapshot,
+    ) -> &mut Self {
+        self.candidates
+            .retain(|addr| before.get(*addr) != after.get(*addr));
+        self
+    }
+
+    /// Keep the cells that didn't change between `before` and `after`.
+    pub fn unchanged(
+        &mut self,
+        before: &Snapshot,
+        after: &Snapshot,
+    ) -> &mut Self {
+        self.candidates
+            .retain(|addr| before.get(*addr) == after.get(*addr));
+        self
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts up in [13] and outputs it, while [14] counts up by 3
+    const PROGRAM: [Intcode; 15] = [
+        1001, 13, 1, 13, // [13] += 1
+        4, 13, // output [13]
+        1001, 14, 3, 14, // [14] += 3
+        1105, 1, 0, // loop
+        0, 0,
+    ];
+
+    #[test]
+    fn diff() {
+        let mut process = Process::new(&PROGRAM);
+        let before = Snapshot::take(&process);
+        process.exec();
+        process.exec();
+        let after = Snapshot::take(&process);
+
+        assert_eq!(
+            before.diff(&after),
+            [
+                Change {
+                    addr: 13,
+                    old: 0,
+                    new: 2
+                },
+                Change {
+                    addr: 14,
+                    old: 0,
+                    new: 3
+                },
+            ]
+        );
+        assert_eq!(after.diff(&after), []);
+    }
+
+    #[test]
+    fn search() {
+        let mut process = Process::new(&PROGRAM);
+        let mut snapshots = Vec::new();
+        for _ in 0..4 {
+            process.exec();
+            snapshots.push(Snapshot::take(&process));
+        }
+
+        // Cells holding each output right after it was made
+        let mut search = Search::new(&snapshots[0]);
+        for (snapshot, value) in snapshots.iter().zip(&process.output) {
+            search.equal(snapshot, *value);
+        }
+        assert_eq!(search.candidates(), [13]);
+
+        // Cells that change between outputs
+        let mut search = Search::new(&snapshots[0]);
+        search.changed(&snapshots[0], &snapshots[1]);
+        assert_eq!(search.candidates(), [13, 14]);
+
+        let mut search = Search::new(&snapshots[0]);
+        search.unchanged(&snapshots[0], &snapshots[1]);
+        assert_eq!(search.candidates().len(), snapshots[0].mem.len() - 2);
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+use crate::disasm::Instruction;
+use crate::intcode::{Intcode, Opcode, Process, Status};
+
+// Call stack reconstruction, for programs that use the relative base as a
+// stack pointer. This is how the puzzle inputs call their functions:
+//
+//     21101, 0, ARG, 1     [rb+1] = ARG       Caller stores the arguments,
+//     21101, RET, 0, 0     [rb+0] = RET       and the return address,
+//     1105, 1, FUNC        jump FUNC          and jumps to the function.
+//
+//     109, N               rb += N            Function opens its frame,
+//     ...                                     uses [rb-N+1].. for its slots,
+//     109, -N              rb -= N            closes the frame,
+//     2106, 0, 0           jump [rb+0]        and returns.
+//
+// So a frame is opened by an increase of the relative base right after a
+// taken jump, and closed when the relative base goes back below it. Moving the
+// relative base away from 0 only sets up the stack, though, so it never opens
+// a frame. None of this is enforced by the machine, so the result is only a
+// best guess.
+
+/// Slots shown for each frame in a backtrace, nearest to `base` first.
+const MAX_SLOTS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Address of the function, where the frame was opened.
+    pub function: usize,
+    /// Address of the jump that called the function, if known.
+    pub call_site: Option<usize>,
+    /// Value found at the bottom of the frame when it was opened.
+    pub return_addr: Intcode,
+    /// Relative base inside the function.
+    pub base: usize,
+    /// Number of slots taken by the frame, below `base`.
+    pub size: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    last_jump: Option<usize>, // Set if the last instruction was a taken jump
+}
+
+// ----------------------------------------------------------------------------
+
+impl CallStack {
+    pub fn new() -> CallStack {
+        Default::default()
+    }
+
+    /// Frames currently open, the innermost one last.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Execute a single instruction with `Process::step()`, keeping track of
+    /// the frames it opens and closes.
+    pub fn step(&mut self, process: &mut Process) -> Option<Status> {
+        let ip = process.ip();
+        let rb = process.rb();
+        let instruction = Instruction::decode(process.mem(), ip);
+        let opcode = instruction.as_ref().map(|i| i.opcode);
+
+        let status = process.step();
+
+        match opcode {
+            Some(Opcode::RBO) if rb == 0 => (),
+            Some(Opcode::RBO) if process.rb() > rb => {
+                let size = process.rb() - rb;
+                match self.last_jump {
+                    Some(call_site) => self.frames.push(Frame {
+                        function: ip,
+                        call_site: Some(call_site),
+                        return_addr: process
+                            .mem()
+                            .get(rb)
+                            .copied()
+                            .unwrap_or(0),
+                        base: process.rb(),
+                        size,
+                    }),
+                    None => {
+                        // The function made room for more slots
+                        if let Some(frame) = self.frames.last_mut() {
+                            frame.base += size;
+                            frame.size += size;
+                        }
+                    }
+                }
+            }
+            Some(Opcode::RBO) => {
+                while let Some(frame) = self.frames.last() {
+                    if process.rb() > frame.base - frame.size {
+                        break;
+                    }
+                    self.frames.pop();
+                }
+            }
+            _ => (),
+        }
+
+        self.last_jump = match instruction {
+            Some(i) if matches!(i.opcode, Opcode::JIT | Opcode::JIF) => {
+                Some(ip).filter(|_| process.ip() != ip + i.size())
+            }
+            _ => None,
+        };
+
+        status
+    }
+
+    /// Describe the open frames like a backtrace, from the innermost one,
+    /// with the values of their last few slots.
+    pub fn backtrace(&self, process: &Process) -> String {
+        let mut result = String::new();
+        let mut ip = process.ip();
+
+        for (n, frame) in self.frames.iter().rev().enumerate() {
+            write!(
+                result,
+                "#{:<3} {:>5} in function {}",
+                n, ip, frame.function
+            )
+            .unwrap();
+            if let Some(call_site) = frame.call_site {
+                write!(result, ", called from {}", call_site).unwrap();
+            }
+            writeln!(result, ", returns to {}", frame.return_addr).unwrap();
+
+            let first = frame.base - frame.size.min(MAX_SLOTS);
+            let mut slots: Vec<String> = (first..frame.base)
+                .map(|addr| {
+                    let offset = addr as Intcode - frame.base as Intcode;
+                    let value = process.mem().get(addr).copied().unwrap_or(0);
+                    format!("[rb{}]={}", offset, value)
+                })
+                .collect();
+            if frame.size > MAX_SLOTS {
+                slots.insert(0, String::from("..."));
+            }
+            writeln!(result, "       {}", slots.join(" ")).unwrap();
+
+            ip = frame.call_site.unwrap_or(frame.function);
+        }
+        writeln!(result, "#{:<3} {:>5} at top level", self.frames.len(), ip)
+            .unwrap();
+
+        result
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Doubles its argument in a function, and outputs the result
+    const PROGRAM: [Intcode; 31] = [
+        109, 100, // rb = 100
+        21101, 0, 10, 1, // [rb+1] = 10
+        21101, 13, 0, 0, // [rb+0] = 13
+        1105, 1, 20, // call 20
+        204, 1, // output [rb+1]
+        99, 0, 0, 0, 0, //
+        109, 2, // function 20: open frame
+        21202, -1, 2, -1, // [rb-1] *= 2
+        109, -2, // close frame
+        2106, 0, 0, // return to [rb+0]
+    ];
+
+    #[test]
+    fn frames() {
+        let mut process = Process::new(&PROGRAM);
+        let mut stack = CallStack::new();
+
+        while process.ip() != 22 {
+            stack.step(&mut process);
+        }
+        assert_eq!(
+            stack.frames(),
+            [Frame {
+                function: 20,
+                call_site: Some(10),
+                return_addr: 13,
+                base: 102,
+                size: 2,
+            }]
+        );
+        assert_eq!(
+            stack.backtrace(&process),
+            "#0      22 in function 20, called from 10, returns to 13\n       \
+             [rb-2]=13 [rb-1]=10\n\
+             #1      10 at top level\n"
+        );
+
+        while stack.step(&mut process) != Some(Status::Halt) {}
+        assert_eq!(stack.depth(), 0);
+        assert_eq!(process.output, [20]);
+    }
+
+    #[test]
+    fn base_outside_memory() {
+        let program = [
+            109, 50, // rb = 50
+            1105, 1, 6,  // jump 6
+            99, //
+            109, 10, // open a frame, with [rb+0] past the end
+            99,
+        ];
+        let mut process = Process::new(&program);
+        let mut stack = CallStack::new();
+
+        while stack.step(&mut process) != Some(Status::Halt) {}
+        assert_eq!(
+            stack.frames(),
+            [Frame {
+                function: 6,
+                call_site: Some(2),
+                return_addr: 0,
+                base: 60,
+                size: 10,
+            }]
+        );
+    }
+}
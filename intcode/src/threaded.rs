@@ -0,0 +1,444 @@
+use std::any::Any;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SendError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::intcode::{Intcode, Process, Status};
+
+// Alternative to driving several processes from a single thread: each process
+// runs on its own OS thread, blocking on a channel whenever it wants input,
+// and sending every output value to the channels connected to it.
+//
+// A machine that has been waiting for input for a whole timeout is idle. If
+// every machine still running is idle at the same time, no values are on
+// their way to any of them, and the host holds no `Input` to send more,
+// nothing can ever wake them up again: the network is deadlocked, and all of
+// them exit.
+//
+// Values on their way are counted as they're sent, and as they're received
+// or left unread, while holding the lock on the shared `Activity`. So a value
+// just received by an idle machine can't be missed, whatever the timing.
+
+/// How each machine of a `Runtime` finished.
+#[derive(Debug, PartialEq)]
+pub enum Exit {
+    /// The program ran until its `HALT` instruction.
+    Halted,
+    /// The program wanted input, but all machines were waiting for input.
+    Deadlocked,
+    /// The program wanted input, but nothing was left that could send it.
+    InputClosed,
+    /// The machine stopped with an error, like a bad opcode.
+    Panicked(String),
+}
+
+/// Sends input values to a machine, from the host.
+pub struct Input {
+    tx: Sender<Intcode>,
+    activity: Arc<Mutex<Activity>>,
+}
+
+struct Machine {
+    process: Process,
+    input: Receiver<Intcode>,
+    /// Inputs of the machines connected to this one.
+    peers: Vec<Sender<Intcode>>,
+    /// Receivers given to the host.
+    outputs: Vec<Sender<Intcode>>,
+}
+
+/// Shared state, to tell idle machines from deadlocked ones.
+#[derive(Default)]
+struct Activity {
+    live: usize,
+    idle: usize,
+    /// Values sent to machines, and not received yet.
+    pending: usize,
+    /// `Input` handles held by the host.
+    hosts: usize,
+    deadlocked: bool,
+}
+
+pub struct Runtime {
+    machines: Vec<Machine>,
+    inputs: Vec<Sender<Intcode>>,
+    activity: Arc<Mutex<Activity>>,
+    timeout: Duration,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Runtime {
+    pub fn new(timeout: Duration) -> Runtime {
+        Runtime {
+            machines: Vec::new(),
+            inputs: Vec::new(),
+            activity: Arc::new(Mutex::new(Activity::default())),
+            timeout,
+        }
+    }
+
+    /// Add a new machine that runs `process`, and return its id.
+    pub fn add(&mut self, process: Process) -> usize {
+        let (tx, rx) = mpsc::channel();
+
+        self.machines.push(Machine {
+            process,
+            input: rx,
+            peers: Vec::new(),
+            outputs: Vec::new(),
+        });
+        self.inputs.push(tx);
+
+        self.machines.len() - 1
+    }
+
+    /// Get a handle to feed input values to a machine. Machines aren't
+    /// deadlocked while the host holds any of these.
+    pub fn input(&self, id: usize) -> Input {
+        self.activity.lock().unwrap().hosts += 1;
+
+        Input {
+            tx: self.inputs[id].clone(),
+            activity: Arc::clone(&self.activity),
+        }
+    }
+
+    /// Send all outputs from machine `from` as inputs to machine `to`.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        let tx = self.inputs[to].clone();
+        self.machines[from].peers.push(tx);
+    }
+
+    /// Get a receiver for all outputs from a machine.
+    pub fn output(&mut self, id: usize) -> Receiver<Intcode> {
+        let (tx, rx) = mpsc::channel();
+        self.machines[id].outputs.push(tx);
+        rx
+    }
+
+    /// Run all machines, each on its own thread, until all of them exit.
+    ///
+    /// Returns how each machine finished, in the same order they were added.
+    /// `Input` handles held by the host must be dropped once done, or the
+    /// machines waiting for them never give up.
+    pub fn run(self) -> Vec<Exit> {
+        let activity = self.activity;
+        activity.lock().unwrap().live = self.machines.len();
+
+        let timeout = self.timeout;
+
+        // Machines only keep the senders that other machines have been given
+        drop(self.inputs);
+
+        let handles: Vec<_> = self
+            .machines
+            .into_iter()
+            .map(|machine| {
+                let activity = Arc::clone(&activity);
+                thread::spawn(move || {
+                    let Machine {
+                        process,
+                        input,
+                        peers,
+                        outputs,
+                    } = machine;
+                    let inbox = Inbox {
+                        input: Some(input),
+                        activity: Arc::clone(&activity),
+                    };
+                    run_machine(process, &inbox, &peers, &outputs, timeout)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| match handle.join() {
+                Ok(exit) => exit,
+                Err(err) => Exit::Panicked(panic_message(err)),
+            })
+            .collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+fn run_machine(
+    mut process: Process,
+    inbox: &Inbox,
+    peers: &[Sender<Intcode>],
+    outputs: &[Sender<Intcode>],
+    timeout: Duration,
+) -> Exit {
+    loop {
+        match process.exec() {
+            Status::Halt => return Exit::Halted,
+            Status::NewOutput => {
+                for value in process.output.drain(..) {
+                    for tx in peers {
+                        // Machines that are gone don't want more values
+                        let _ = send(tx, value, &inbox.activity);
+                    }
+                    for tx in outputs {
+                        let _ = tx.send(value);
+                    }
+                }
+            }
+            Status::WantInput => match inbox.wait(timeout) {
+                Ok(value) => process.input.push(value),
+                Err(exit) => return exit,
+            },
+        }
+    }
+}
+
+/// Send a value to a machine, counting it until it's received.
+fn send(
+    tx: &Sender<Intcode>,
+    value: Intcode,
+    activity: &Mutex<Activity>,
+) -> Result<(), SendError<Intcode>> {
+    let mut activity = activity.lock().unwrap();
+    tx.send(value)?;
+    activity.pending += 1;
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Input channel of a running machine. Keeps count of live machines and of
+/// values left unread, even when the machine panics.
+struct Inbox {
+    /// Only taken once the machine is done.
+    input: Option<Receiver<Intcode>>,
+    activity: Arc<Mutex<Activity>>,
+}
+
+impl Inbox {
+    /// Block until an input value arrives, keeping track of idle time.
+    fn wait(&self, timeout: Duration) -> Result<Intcode, Exit> {
+        let input = self.input.as_ref().expect("input");
+        let mut idle = false;
+
+        let result = loop {
+            match input.recv_timeout(timeout) {
+                Ok(value) => break Ok(value),
+                Err(RecvTimeoutError::Disconnected) => {
+                    // Senders are also gone when their machines deadlocked
+                    if self.activity.lock().unwrap().deadlocked {
+                        break Err(Exit::Deadlocked);
+                    }
+                    break Err(Exit::InputClosed);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let mut activity = self.activity.lock().unwrap();
+                    if !idle {
+                        idle = true;
+                        activity.idle += 1;
+                    }
+                    if activity.idle == activity.live
+                        && activity.pending == 0
+                        && activity.hosts == 0
+                    {
+                        activity.deadlocked = true;
+                    }
+                    if activity.deadlocked {
+                        break Err(Exit::Deadlocked);
+                    }
+                }
+            }
+        };
+
+        let mut activity = self.activity.lock().unwrap();
+        if idle {
+            activity.idle -= 1;
+        }
+        if result.is_ok() {
+            activity.pending -= 1;
+        }
+
+        result
+    }
+}
+
+impl Drop for Inbox {
+    fn drop(&mut self) {
+        let mut activity = match self.activity.lock() {
+            Ok(activity) => activity,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        activity.live -= 1;
+
+        // Closed while locked, so no more values can be sent and go unread
+        if let Some(input) = self.input.take() {
+            activity.pending -= input.try_iter().count();
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Input {
+    pub fn send(&self, value: Intcode) -> Result<(), SendError<Intcode>> {
+        send(&self.tx, value, &self.activity)
+    }
+}
+
+impl Clone for Input {
+    fn clone(&self) -> Input {
+        self.activity.lock().unwrap().hosts += 1;
+
+        Input {
+            tx: self.tx.clone(),
+            activity: Arc::clone(&self.activity),
+        }
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        let mut activity = match self.activity.lock() {
+            Ok(activity) => activity,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        activity.hosts -= 1;
+    }
+}
+
+fn panic_message(err: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = err.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = err.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        String::from("Unknown error")
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only how often waiting machines check for a deadlock: values on their
+    // way are counted, so results don't depend on it
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    // Amplifier program from Day 7, Part Two examples
+    const AMPLIFIER: [Intcode; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27,
+        1001, 28, -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    /// Feedback loop, run cooperatively on a single thread.
+    fn feedback_loop(program: &[Intcode], phases: &[Intcode]) -> Intcode {
+        let mut processes: Vec<_> = phases
+            .iter()
+            .map(|phase| {
+                let mut process = Process::new(program);
+                process.input.push(*phase);
+                process
+            })
+            .collect();
+
+        let mut next_input = 0;
+
+        for i in (0..phases.len()).cycle() {
+            processes[i].input.push(next_input);
+            if processes[i].exec() == Status::Halt {
+                break;
+            }
+            next_input = processes[i].output.pop().expect("No output");
+        }
+
+        next_input
+    }
+
+    /// Feedback loop, run with one thread per amplifier.
+    fn threaded_loop(program: &[Intcode], phases: &[Intcode]) -> Intcode {
+        let mut runtime = Runtime::new(TIMEOUT);
+
+        for phase in phases {
+            let id = runtime.add(Process::new(program));
+            runtime.input(id).send(*phase).unwrap();
+        }
+        for id in 0..phases.len() {
+            runtime.connect(id, (id + 1) % phases.len());
+        }
+        runtime.input(0).send(0).unwrap();
+        let thruster = runtime.output(phases.len() - 1);
+
+        let exits = runtime.run();
+        assert!(exits.iter().all(|exit| exit == &Exit::Halted));
+
+        thruster.try_iter().last().expect("No output")
+    }
+
+    #[test]
+    fn same_as_cooperative() {
+        let mut phases = vec![9, 8, 7, 6, 5];
+
+        assert_eq!(threaded_loop(&AMPLIFIER, &phases), 139629729);
+
+        // Try a few different phase orders
+        for _ in 0..5 {
+            phases.rotate_left(2);
+            phases.swap(0, 1);
+            assert_eq!(
+                threaded_loop(&AMPLIFIER, &phases),
+                feedback_loop(&AMPLIFIER, &phases)
+            );
+        }
+    }
+
+    #[test]
+    fn deadlock() {
+        // Two machines that both start by waiting for the other one
+        let program = [3, 9, 4, 9, 99];
+        let mut runtime = Runtime::new(TIMEOUT);
+        let a = runtime.add(Process::new(&program));
+        let b = runtime.add(Process::new(&program));
+        runtime.connect(a, b);
+        runtime.connect(b, a);
+
+        assert_eq!(runtime.run(), [Exit::Deadlocked, Exit::Deadlocked]);
+    }
+
+    #[test]
+    fn host_input() {
+        // Output 1, then echo an input value
+        let program = [104, 1, 3, 9, 4, 9, 99, 0, 0, 0];
+        let mut runtime = Runtime::new(Duration::from_millis(1));
+        let id = runtime.add(Process::new(&program));
+        let input = runtime.input(id);
+        let output = runtime.output(id);
+
+        let host = thread::spawn(move || {
+            // Once the machine is waiting, give it time to be idle for many
+            // timeouts: it's never deadlocked while the host can still send
+            assert_eq!(output.recv(), Ok(1));
+            thread::sleep(Duration::from_millis(20));
+            input.send(42).unwrap();
+            output.recv()
+        });
+
+        assert_eq!(runtime.run(), [Exit::Halted]);
+        assert_eq!(host.join().unwrap(), Ok(42));
+    }
+
+    #[test]
+    fn exit_statuses() {
+        let mut runtime = Runtime::new(TIMEOUT);
+        runtime.add(Process::new(&[104, 1, 99])); // Output, halt
+        runtime.add(Process::new(&[3, 0, 99])); // Input from nobody
+        runtime.add(Process::new(&[42])); // Bad opcode
+
+        let exits = runtime.run();
+        assert_eq!(exits[0], Exit::Halted);
+        assert_eq!(exits[1], Exit::InputClosed);
+        assert_eq!(exits[2], Exit::Panicked(String::from("Bad Opcode")));
+    }
+}
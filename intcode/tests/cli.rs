@@ -0,0 +1,66 @@
+// Command-line tool on programs and inputs it can't run: it should report
+// them and exit with an error, instead of panicking.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Adds 1 and 1 into the `77` after it, which is not a valid instruction.
+const FAULTY: &str = "1101,1,1,5,77,99";
+
+/// Run the tool with a program saved under `name`, feeding `stdin` to it.
+fn intcode(name: &str, args: &[&str], program: &str, stdin: &str) -> Output {
+    let path = env::temp_dir().join(format!("intcode-cli-{}.txt", name));
+    fs::write(&path, program).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_intcode"))
+        .args(args)
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    fs::remove_file(&path).unwrap();
+    output
+}
+
+fn assert_error(output: &Output, msg: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains(msg), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn invalid_instruction() {
+    for command in &["run", "trace", "profile"] {
+        let output = intcode(command, &[command], FAULTY, "");
+        assert_error(&output, "Invalid instruction 77 at address 4");
+    }
+
+    let output = intcode("diff", &["diff", "--patch", "1=2"], FAULTY, "");
+    assert_error(&output, "Invalid instruction 77 at address 4");
+
+    // The debugger stops there, and lets the program be fixed
+    let output = intcode("debug", &["debug"], FAULTY, "c\nset 4 99\nc\nq\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Invalid instruction 77 at address 4"));
+    assert!(stdout.contains("Program halted"));
+}
+
+#[test]
+fn invalid_input() {
+    let output = intcode("input", &["run"], "3,0,99", "abc\n");
+    assert_error(&output, "Bad input value 'abc'");
+}
@@ -0,0 +1,59 @@
+use intcode::{Process, Status};
+use intcode_conformance::{
+    assert_conforms, check, Event, Intcode, Level, Machine,
+};
+
+struct Tool(Process);
+
+impl Machine for Tool {
+    fn load(program: &[Intcode]) -> Tool {
+        Tool(Process::new(program))
+    }
+
+    fn input(&mut self, value: Intcode) {
+        self.0.input.push(value);
+    }
+
+    fn resume(&mut self) -> Event {
+        match self.0.exec() {
+            Status::NewOutput => Event::Output(self.0.output.remove(0)),
+            Status::WantInput => Event::NeedInput,
+            Status::Halt => Event::Halt,
+        }
+    }
+}
+
+#[test]
+fn conforms() {
+    assert_conforms::<Tool>(Level::Day09);
+}
+
+/// VM that forgets about relative mode, to check that the suite notices.
+struct NoRelative(Tool);
+
+impl Machine for NoRelative {
+    fn load(program: &[Intcode]) -> NoRelative {
+        let program: Vec<_> = program
+            .iter()
+            .map(|v| if (v / 100) % 10 == 2 { v - 200 } else { *v })
+            .collect();
+        NoRelative(Tool::load(&program))
+    }
+
+    fn input(&mut self, value: Intcode) {
+        self.0.input(value);
+    }
+
+    fn resume(&mut self) -> Event {
+        self.0.resume()
+    }
+}
+
+#[test]
+fn catches_failures() {
+    assert!(check::<NoRelative>(Level::Day05).is_empty());
+
+    let failures = check::<NoRelative>(Level::Day09);
+    assert!(failures.iter().any(|f| f.case == "relative_input"));
+    assert!(failures.iter().all(|f| f.case != "large_mul"));
+}
@@ -0,0 +1,277 @@
+use std::io::{self, Write};
+
+use crate::Palette;
+
+// Animated GIF encoder, for palettes of up to 256 colors.
+//
+// Each frame is a full image, compressed with variable-length LZW codes and
+// shown for a fixed delay; the NETSCAPE2.0 extension makes it loop forever.
+// See https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+
+const MAX_CODES: u16 = 4096;
+
+pub struct Encoder<'a, W: Write> {
+    out: &'a mut W,
+    width: u16,
+    height: u16,
+    min_code_size: u8,
+}
+
+/// Packs variable-length codes, least significant bit first, into data
+/// sub-blocks of up to 255 bytes.
+struct BitWriter {
+    blocks: Vec<u8>,
+    block: Vec<u8>,
+    bits: u32,
+    count: u8,
+}
+
+// ----------------------------------------------------------------------------
+
+impl<'a, W: Write> Encoder<'a, W> {
+    /// Write the header, global palette and looping extension.
+    pub fn new(
+        out: &'a mut W,
+        width: u16,
+        height: u16,
+        palette: &Palette,
+    ) -> io::Result<Encoder<'a, W>> {
+        // The color table has 2^(size + 1) entries
+        let size = (0..8).find(|s| 2 << s >= palette.len()).unwrap_or(7);
+
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        out.write_all(&[0x80 | 0x70 | size as u8, 0, 0])?;
+        for i in 0..2 << size {
+            out.write_all(&palette.0.get(i).copied().unwrap_or([0; 3]))?;
+        }
+
+        out.write_all(&[0x21, 0xff, 11])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[3, 1, 0, 0, 0])?;
+
+        Ok(Encoder {
+            out,
+            width,
+            height,
+            min_code_size: (size as u8 + 1).max(2),
+        })
+    }
+
+    /// Write a frame, with one palette index per pixel, shown for
+    /// `delay_cs` hundredths of a second.
+    pub fn frame(&mut self, pixels: &[u8], delay_cs: u16) -> io::Result<()> {
+        assert_eq!(pixels.len(), self.width as usize * self.height as usize);
+
+        // Graphic control extension, to leave the frame in place
+        self.out.write_all(&[0x21, 0xf9, 4, 0x04])?;
+        self.out.write_all(&delay_cs.to_le_bytes())?;
+        self.out.write_all(&[0, 0])?;
+
+        // Image descriptor, covering the whole screen
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0])?;
+
+        self.out.write_all(&[self.min_code_size])?;
+        self.out.write_all(&lzw(pixels, self.min_code_size))
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.out.write_all(&[0x3b])
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Compress pixels into LZW data sub-blocks, ending with a terminator.
+fn lzw(pixels: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::new();
+    // Code for each prefix code followed by a pixel, or 0 if there's none yet
+    let mut codes = vec![0u16; (MAX_CODES as usize) << min_code_size];
+    let code_index = |prefix: u16, pixel: u8| {
+        assert!((pixel as u16) < clear, "Pixel out of the palette");
+        ((prefix as usize) << min_code_size) | pixel as usize
+    };
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+
+    writer.write(clear, size);
+
+    let mut pixels = pixels.iter();
+    let mut prefix = match pixels.next() {
+        Some(pixel) => *pixel as u16,
+        None => {
+            writer.write(end, size);
+            return writer.finish();
+        }
+    };
+
+    for pixel in pixels {
+        let index = code_index(prefix, *pixel);
+        if codes[index] != 0 {
+            prefix = codes[index];
+            continue;
+        }
+
+        writer.write(prefix, size);
+        if next == MAX_CODES {
+            // Table full, start over
+            writer.write(clear, size);
+            codes.iter_mut().for_each(|code| *code = 0);
+            next = end + 1;
+            size = min_code_size + 1;
+        } else {
+            if next >= 1 << size {
+                size += 1;
+            }
+            codes[index] = next;
+            next += 1;
+        }
+        prefix = *pixel as u16;
+    }
+
+    writer.write(prefix, size);
+    writer.write(end, size);
+    writer.finish()
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            blocks: Vec::new(),
+            block: Vec::with_capacity(255),
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, size: u8) {
+        self.bits |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.block.push(byte);
+        if self.block.len() == 255 {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.block.is_empty() {
+            self.blocks.push(self.block.len() as u8);
+            self.blocks.append(&mut self.block);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.push(self.bits as u8);
+        }
+        self.flush();
+        self.blocks.push(0);
+        self.blocks
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode LZW data sub-blocks, to check the encoder against.
+    fn unlzw(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut data = data;
+        while data[0] != 0 {
+            let len = data[0] as usize;
+            bytes.extend_from_slice(&data[1..=len]);
+            data = &data[len + 1..];
+        }
+
+        let clear = 1usize << min_code_size;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut size = min_code_size + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut result = Vec::new();
+        let (mut bits, mut count, mut pos) = (0u32, 0, 0);
+
+        loop {
+            while count < size {
+                bits |= (bytes[pos] as u32) << count;
+                pos += 1;
+                count += 8;
+            }
+            let code = (bits & ((1 << size) - 1)) as usize;
+            bits >>= size;
+            count -= size;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                size = min_code_size + 1;
+                prev = None;
+                continue;
+            } else if code == clear + 1 {
+                return result;
+            }
+
+            let entry = match (table.get(code), &prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => {
+                    let mut entry = prev.clone();
+                    entry.push(prev[0]);
+                    entry
+                }
+                (None, None) => panic!("Bad code"),
+            };
+            result.extend_from_slice(&entry);
+            if let Some(mut prev) = prev {
+                prev.push(entry[0]);
+                table.push(prev);
+            }
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        // Long enough to fill the table and start over
+        let pixels: Vec<u8> =
+            (0..100_000u64).map(|i| ((i * i / 7) % 5) as u8).collect();
+
+        for min_code_size in 3..=8 {
+            let data = lzw(&pixels, min_code_size);
+            assert_eq!(unlzw(&data, min_code_size), pixels);
+        }
+        assert_eq!(unlzw(&lzw(&[], 2), 2), []);
+    }
+
+    #[test]
+    fn header() {
+        let palette = Palette(vec![[0, 0, 0], [255, 255, 255], [255, 0, 0]]);
+        let mut out = Vec::new();
+        let encoder = Encoder::new(&mut out, 3, 2, &palette).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(&out[..13], b"GIF89a\x03\x00\x02\x00\xf1\x00\x00");
+        assert_eq!(&out[13..25], [0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 0]);
+        assert_eq!(out.last(), Some(&0x3b));
+    }
+}
@@ -0,0 +1,230 @@
+// Headless recording of grid-based visualisations, like the arcade screen or
+// the hull painted by the robot, as an animated GIF or a sequence of PNGs.
+//
+// Each frame is a grid of cells, each holding an index into the palette, and
+// is scaled up by the cell size when encoded. Both encoders are written from
+// scratch, so recording needs no display and no extra dependencies.
+
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+mod gif;
+mod png;
+
+pub type Color = [u8; 3];
+
+/// Colors for each cell value, up to 256.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette(pub Vec<Color>);
+
+/// A grid of cells, by row, each holding an index into the palette.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+pub struct Recorder {
+    palette: Palette,
+    cell_size: usize,
+    delay_ms: u32,
+    frames: Vec<Frame>,
+}
+
+// ----------------------------------------------------------------------------
+
+impl Palette {
+    /// Parse a comma-separated list of colors, as `rrggbb` or `#rrggbb`.
+    pub fn parse(text: &str) -> Option<Palette> {
+        let colors: Option<Vec<Color>> = text
+            .split(',')
+            .map(|s| {
+                let s = s.trim().trim_start_matches('#');
+                if s.len() != 6 {
+                    return None;
+                }
+                let channel = |i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok();
+                Some([channel(0)?, channel(2)?, channel(4)?])
+            })
+            .collect();
+
+        match colors {
+            Some(colors) if !colors.is_empty() && colors.len() <= 256 => {
+                Some(Palette(colors))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Frame {
+    /// New frame, with all cells set to the first color in the palette.
+    pub fn new(width: usize, height: usize) -> Frame {
+        Frame {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Value of a cell, with cells outside the frame being 0.
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x]
+        } else {
+            0
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: u8) {
+        assert!(x < self.width && y < self.height, "Cell out of the frame");
+        self.cells[y * self.width + x] = value;
+    }
+
+    /// Pixels of the frame, scaled up by `cell_size`, and padded with 0 up to
+    /// `width` by `height` cells.
+    fn pixels(&self, width: usize, height: usize, cell_size: usize) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(width * height * cell_size.pow(2));
+
+        for y in 0..height * cell_size {
+            for x in 0..width * cell_size {
+                pixels.push(self.get(x / cell_size, y / cell_size));
+            }
+        }
+
+        pixels
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl Recorder {
+    /// New recorder, with 4x4 pixel cells and 50 ms between frames.
+    pub fn new(palette: Palette) -> Recorder {
+        assert!(!palette.is_empty(), "Empty palette");
+
+        Recorder {
+            palette,
+            cell_size: 4,
+            delay_ms: 50,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Size in pixels of the side of each cell.
+    pub fn cell_size(mut self, cell_size: usize) -> Recorder {
+        assert!(cell_size > 0, "Empty cells");
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Delay between frames in animations. GIF delays are rounded down to
+    /// hundredths of a second.
+    pub fn delay_ms(mut self, delay_ms: u32) -> Recorder {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Size in cells of the largest frame, which all frames are padded to.
+    fn size(&self) -> (usize, usize) {
+        let width = self.frames.iter().map(Frame::width).max().unwrap_or(0);
+        let height = self.frames.iter().map(Frame::height).max().unwrap_or(0);
+        (width.max(1), height.max(1))
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Encode all frames as an animated GIF, looping forever.
+    pub fn write_gif<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let (width, height) = self.size();
+        let too_large =
+            |_| io::Error::new(io::ErrorKind::InvalidInput, "Frames too large");
+        let width_px =
+            u16::try_from(width * self.cell_size).map_err(too_large)?;
+        let height_px =
+            u16::try_from(height * self.cell_size).map_err(too_large)?;
+
+        let mut encoder =
+            gif::Encoder::new(out, width_px, height_px, &self.palette)?;
+        let delay_cs = (self.delay_ms / 10).min(u16::MAX as u32) as u16;
+        for frame in &self.frames {
+            let pixels = frame.pixels(width, height, self.cell_size);
+            encoder.frame(&pixels, delay_cs)?;
+        }
+        encoder.finish()
+    }
+
+    /// Encode a single frame as a PNG image.
+    pub fn write_png<W: Write>(
+        &self,
+        index: usize,
+        out: &mut W,
+    ) -> io::Result<()> {
+        let (width, height) = self.size();
+        let pixels = self.frames[index].pixels(width, height, self.cell_size);
+
+        png::write(
+            out,
+            width * self.cell_size,
+            height * self.cell_size,
+            &self.palette,
+            &pixels,
+        )
+    }
+
+    /// Save the recording to `path`: as an animated GIF if it ends in `.gif`,
+    /// or else as a directory of numbered PNG images, one per frame.
+    ///
+    /// Returns the paths of the files written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<PathBuf>> {
+        let path = path.as_ref();
+
+        if path.extension().and_then(|ext| ext.to_str()) == Some("gif") {
+            let mut out = BufWriter::new(File::create(path)?);
+            self.write_gif(&mut out)?;
+            out.flush()?;
+            return Ok(vec![path.to_path_buf()]);
+        }
+
+        fs::create_dir_all(path)?;
+        let mut paths = Vec::with_capacity(self.frames.len());
+        for index in 0..self.frames.len() {
+            let file = path.join(format!("frame-{:05}.png", index));
+            let mut out = BufWriter::new(File::create(&file)?);
+            self.write_png(index, &mut out)?;
+            out.flush()?;
+            paths.push(file);
+        }
+
+        Ok(paths)
+    }
+}
+
+// ----------------------------------------------------------------------------
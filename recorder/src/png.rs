@@ -0,0 +1,173 @@
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+use crate::Palette;
+
+// PNG encoder, for images with a palette of up to 256 colors.
+//
+// Image data isn't compressed: it's wrapped in stored deflate blocks, which
+// is enough for a valid zlib stream. Frames are small, so this keeps things
+// simple at the cost of some disk space.
+// See https://www.w3.org/TR/png/
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest length of a stored deflate block.
+const MAX_STORED: usize = 0xffff;
+
+// ----------------------------------------------------------------------------
+
+/// Write an image with one palette index per pixel, by row.
+pub fn write<W: Write>(
+    out: &mut W,
+    width: usize,
+    height: usize,
+    palette: &Palette,
+    pixels: &[u8],
+) -> io::Result<()> {
+    assert_eq!(pixels.len(), width * height);
+    let too_large =
+        |_| io::Error::new(io::ErrorKind::InvalidInput, "Image too large");
+
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(&u32::try_from(width).map_err(too_large)?.to_be_bytes());
+    header.extend(&u32::try_from(height).map_err(too_large)?.to_be_bytes());
+    header.extend(&[8, 3, 0, 0, 0]); // 8 bits per pixel, indexed color
+    chunk(out, b"IHDR", &header)?;
+
+    let colors: Vec<u8> = palette.0.iter().flatten().copied().collect();
+    chunk(out, b"PLTE", &colors)?;
+
+    // Each row starts with its filter type, 0 for none
+    let mut data = Vec::with_capacity((width + 1) * height);
+    for row in pixels.chunks(width.max(1)) {
+        data.push(0);
+        data.extend_from_slice(row);
+    }
+    chunk(out, b"IDAT", &zlib_stored(&data))?;
+
+    chunk(out, b"IEND", &[])
+}
+
+fn chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&crc.value().to_be_bytes())
+}
+
+// ----------------------------------------------------------------------------
+
+/// Wrap data in a zlib stream, without compressing it.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = (data.len() / MAX_STORED + 1) * 5;
+    let mut result = Vec::with_capacity(data.len() + blocks + 6);
+
+    result.extend(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_STORED).peekable();
+    if blocks.peek().is_none() {
+        result.extend(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        result.push(last as u8);
+        result.extend(&len.to_le_bytes());
+        result.extend(&(!len).to_le_bytes());
+        result.extend_from_slice(block);
+    }
+
+    result.extend(&adler32(data).to_be_bytes());
+    result
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    // Sums can go this far before they need to be reduced
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        let mut table = [0; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+
+        Crc32 {
+            table,
+            crc: 0xffff_ffff,
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            let index = (self.crc ^ *byte as u32) & 0xff;
+            self.crc = self.table[index as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    fn value(&self) -> u32 {
+        self.crc ^ 0xffff_ffff
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        let mut crc = Crc32::new();
+        crc.update(b"IEND");
+        assert_eq!(crc.value(), 0xae42_6082);
+
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&vec![0xff; 100_000]), 0x149a_302c);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let data = vec![7; MAX_STORED + 10];
+        let stream = zlib_stored(&data);
+
+        assert_eq!(stream.len(), 2 + 5 + MAX_STORED + 5 + 10 + 4);
+        assert_eq!(&stream[2..7], [0, 0xff, 0xff, 0, 0]);
+        assert_eq!(
+            &stream[MAX_STORED + 7..MAX_STORED + 12],
+            [1, 10, 0, 0xf5, 0xff]
+        );
+    }
+}